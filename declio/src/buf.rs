@@ -0,0 +1,275 @@
+//! Integration with the [`bytes`] crate.
+
+use crate::ctx::{Len, ToEnd};
+use crate::trace::Unread;
+use crate::{limits, Decode, Encode, Error};
+use bytes::{Buf, BufMut, Bytes};
+use std::convert::TryFrom;
+use std::io;
+
+/// Decodes a value from a buffer with the given context, advancing it past the decoded bytes.
+///
+/// Unlike [`from_bytes`](crate::from_bytes), bytes left over after the value are not an error;
+/// they stay in the buffer.
+///
+/// [`Bytes`] fields are copied out of the buffer; use [`decode_bytes`] to share them with the
+/// input instead.
+pub fn decode_buf<T, Ctx, B>(buf: &mut B, ctx: Ctx) -> Result<T, Error>
+where
+    T: Decode<Ctx>,
+    B: Buf,
+{
+    T::decode(ctx, &mut buf.reader())
+}
+
+/// Encodes a value into a buffer with the given context.
+///
+/// Buffers with a fixed capacity, like `&mut [u8]`, return an error if the value does not fit, in
+/// which case part of it may have been written.
+pub fn encode_buf<T, Ctx, B>(value: &T, ctx: Ctx, buf: &mut B) -> Result<(), Error>
+where
+    T: Encode<Ctx> + ?Sized,
+    B: BufMut,
+{
+    value.encode(ctx, &mut buf.writer())
+}
+
+/// Decodes a value from [`Bytes`] with the given context, advancing them past the decoded bytes.
+///
+/// This is like [`decode_buf`], except that [`Bytes`] fields of the value are slices of the input,
+/// which share its memory instead of copying it. This includes fields inside of a `byte_len` field
+/// or a sequence, and one level of nesting of those, like a `byte_len` field inside of another or
+/// a sequence inside of a `byte_len` field. Fields that are nested more deeply, or inside of custom
+/// decoders that wrap the reader in their own type, are still decoded correctly, but get a copy
+/// instead. On error, `bytes` is left unchanged.
+///
+/// # Example
+///
+/// ```
+/// use bytes::Bytes;
+/// use declio::ctx::Len;
+/// use declio::Decode;
+///
+/// #[derive(Decode)]
+/// struct Packet {
+///     len: u8,
+///     #[declio(ctx = "Len(*len as usize)")]
+///     payload: Bytes,
+/// }
+///
+/// let mut input = Bytes::from_static(&[0x02, 0xaa, 0xbb, 0xcc]);
+/// let packet: Packet = declio::decode_bytes(&mut input, ()).unwrap();
+/// assert_eq!(packet.payload, [0xaa, 0xbb][..]);
+/// assert_eq!(packet.payload.as_ptr(), input.as_ptr().wrapping_sub(2));
+/// assert_eq!(input, [0xcc][..]);
+/// ```
+pub fn decode_bytes<T, Ctx>(bytes: &mut Bytes, ctx: Ctx) -> Result<T, Error>
+where
+    T: Decode<Ctx>,
+{
+    let mut reader = SourceReader {
+        bytes: bytes.clone(),
+        pos: 0,
+    };
+    let value = T::decode(ctx, &mut reader)?;
+    bytes.advance(reader.pos);
+    Ok(value)
+}
+
+/// A reader over the input of `decode_bytes`.
+struct SourceReader {
+    bytes: Bytes,
+    pos: usize,
+}
+
+impl io::Read for SourceReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = io::Read::read(&mut &self.bytes[self.pos..], buf)?;
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// A reader over part of the input of `decode_bytes`, which can give out slices of it.
+trait Source {
+    /// The number of bytes left to read.
+    fn remaining(&self) -> usize;
+
+    /// Moves back over the last `len` bytes that were read.
+    fn unread(&mut self, len: usize);
+
+    /// Returns the next `len` bytes, which must not be more than are remaining, as a slice of the
+    /// input, and advances past them.
+    fn slice(&mut self, len: usize) -> Bytes;
+}
+
+impl Source for SourceReader {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn unread(&mut self, len: usize) {
+        self.pos -= len;
+    }
+
+    fn slice(&mut self, len: usize) -> Bytes {
+        let slice = self.bytes.slice(self.pos..self.pos + len);
+        self.pos += len;
+        slice
+    }
+}
+
+/// The reader of `byte_len` fields and `ByteLen` sequences.
+impl<S> Source for io::Take<&mut S>
+where
+    S: Source,
+{
+    fn remaining(&self) -> usize {
+        let limit = usize::try_from(self.limit()).unwrap_or(usize::MAX);
+        limit.min(self.get_ref().remaining())
+    }
+
+    fn unread(&mut self, len: usize) {
+        self.set_limit(self.limit() + len as u64);
+        self.get_mut().unread(len);
+    }
+
+    fn slice(&mut self, len: usize) -> Bytes {
+        self.set_limit(self.limit() - len as u64);
+        self.get_mut().slice(len)
+    }
+}
+
+/// The reader of elements decoded until the end of the stream or a terminator, which starts with
+/// a byte that was read ahead from the inner reader.
+impl<S> Source for io::Chain<&mut Unread<'_>, &mut S>
+where
+    S: Source,
+{
+    fn remaining(&self) -> usize {
+        let (unread, inner) = self.get_ref();
+        unread.len() + inner.remaining()
+    }
+
+    fn unread(&mut self, len: usize) {
+        let (unread, inner) = self.get_mut();
+        inner.unread(unread.len() + len);
+        unread.clear();
+    }
+
+    fn slice(&mut self, len: usize) -> Bytes {
+        self.unread(0);
+        self.get_mut().1.slice(len)
+    }
+}
+
+/// Returns the reader as a [`Source`], if it is one of the readers that `decode_bytes` and the
+/// built-in implementations create.
+///
+/// Decoders can't require their reader to implement `Source`, so this compares its type against
+/// those readers instead, with lifetimes erased. Only the combinations listed here are recognized;
+/// any other reader, like a `Take` of a `Take` of a `Chain`, or a `Take<&[u8]>` made by a custom
+/// decoder, returns `None`, and `read_bytes` falls back to copying.
+fn as_source<R>(reader: &mut R) -> Option<&mut dyn Source>
+where
+    R: io::Read,
+{
+    type Take<'a, R> = io::Take<&'a mut R>;
+    type Chain<'a, R> = io::Chain<&'a mut Unread<'a>, &'a mut R>;
+
+    macro_rules! try_source {
+        ($($ty:ty),*) => {$(
+            if typeid::of::<R>() == typeid::of::<$ty>() {
+                // SAFETY: `R` is `$ty`, except maybe for its lifetimes, which outlive the borrow
+                // of `reader` that is returned.
+                return Some(unsafe { &mut *(reader as *mut R as *mut $ty) });
+            }
+        )*};
+    }
+
+    try_source!(
+        SourceReader,
+        Take<SourceReader>,
+        Chain<SourceReader>,
+        Take<Take<SourceReader>>,
+        Take<Chain<SourceReader>>,
+        Chain<Take<SourceReader>>
+    );
+    None
+}
+
+/// Reads `len` bytes, or all bytes until the end of the stream if `len` is `None`.
+///
+/// The result is a slice of the input of `decode_bytes` if the reader is one of its readers, or a
+/// copy otherwise.
+fn read_bytes<R>(len: Option<usize>, reader: &mut R) -> Result<Bytes, Error>
+where
+    R: io::Read,
+{
+    let source = match as_source(reader) {
+        Some(source) => source,
+        None => {
+            let vec: Vec<u8> = match len {
+                Some(len) => Decode::decode(Len(len), reader)?,
+                None => Decode::decode(ToEnd, reader)?,
+            };
+            return Ok(Bytes::from(vec));
+        }
+    };
+    let len = match len {
+        Some(len) if len > source.remaining() => {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+        }
+        Some(len) => len,
+        None => source.remaining(),
+    };
+    limits::check_len(len)?;
+    Ok(source.slice(len))
+}
+
+impl Decode<Len> for Bytes {
+    /// Decodes the given number of bytes.
+    ///
+    /// With [`decode_bytes`], the result is a slice of its input.
+    fn decode<R>(Len(len): Len, reader: &mut R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        read_bytes(Some(len), reader)
+    }
+}
+
+impl Decode<ToEnd> for Bytes {
+    /// Decodes all bytes until the end of the stream.
+    ///
+    /// With [`decode_bytes`], the result is a slice of its input.
+    fn decode<R>(_: ToEnd, reader: &mut R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        read_bytes(None, reader)
+    }
+}
+
+impl Encode<Len> for Bytes {
+    /// Encodes the bytes as they are.
+    ///
+    /// The length context is provided as a sanity check to protect against logic errors; if it is
+    /// not equal to the number of bytes, then this function will return an error.
+    fn encode<W>(&self, ctx: Len, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self[..].encode(ctx, writer)
+    }
+}
+
+impl Encode<ToEnd> for Bytes {
+    /// Encodes the bytes as they are.
+    fn encode<W>(&self, _: ToEnd, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self[..].encode(ToEnd, writer)
+    }
+}
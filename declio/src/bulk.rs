@@ -0,0 +1,181 @@
+//! Faster paths for sequences of primitives.
+//!
+//! The implementations for slices, vectors and arrays are generic over their element type, and
+//! stable Rust can't specialize them for primitives. Instead, the functions in the `seq` module
+//! use [`specialize`] to check whether the elements are primitives with an `Endian` context (or
+//! `u8`/`i8` with the unit context), and if so, read or write all of them at once.
+
+use crate::ctx::Endian;
+use crate::{from_big_endian, limits, to_big_endian, Error};
+use std::any::TypeId;
+use std::mem::{self, ManuallyDrop};
+use std::{io, slice};
+
+/// Returns from the enclosing function with `$body` if `$t` is a primitive, and `$ctx` is a
+/// context that the primitive supports. In `$body`, `$p` is the primitive type, and `$endian` the
+/// byte order given by the context.
+macro_rules! specialize {
+    ($t:ty, $ctx:expr, |$p:ident, $endian:ident| $body:expr) => {
+        $crate::bulk::specialize!(
+            @each $t, $ctx, $p, $endian, $body,
+            u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64
+        )
+    };
+
+    (@each $t:ty, $ctx:expr, $p:ident, $endian:ident, $body:expr, $($prim:ty)*) => {$(
+        if let Some($endian) = $crate::bulk::primitive_endian::<$t, _, $prim>($ctx) {
+            type $p = $prim;
+            return $body;
+        }
+    )*};
+}
+pub(crate) use specialize;
+
+/// Returns the byte order given by `ctx`, if `T` is the primitive `P`, and `Ctx` is a context
+/// that `P` supports.
+pub(crate) fn primitive_endian<T, Ctx, P>(ctx: &Ctx) -> Option<Endian>
+where
+    P: Primitive,
+{
+    // Primitives have no lifetime parameters, so comparing the type IDs with lifetimes erased is
+    // enough to know that the types are the same.
+    if typeid::of::<T>() != TypeId::of::<P>() {
+        None
+    } else if typeid::of::<Ctx>() == TypeId::of::<Endian>() {
+        // SAFETY: `Ctx` is `Endian`.
+        Some(unsafe { *(ctx as *const Ctx as *const Endian) })
+    } else if typeid::of::<Ctx>() == TypeId::of::<()>() && mem::size_of::<P>() == 1 {
+        Some(Endian::Big)
+    } else {
+        None
+    }
+}
+
+/// Casts a slice to a slice of another element type.
+///
+/// # Safety
+///
+/// `T` and `U` must be the same type.
+pub(crate) unsafe fn cast_slice<T, U>(slice: &[T]) -> &[U] {
+    slice::from_raw_parts(slice.as_ptr() as *const U, slice.len())
+}
+
+/// Casts a vector to a vector of another element type.
+///
+/// # Safety
+///
+/// `T` and `U` must be the same type.
+pub(crate) unsafe fn cast_vec<T, U>(vec: Vec<T>) -> Vec<U> {
+    let mut vec = ManuallyDrop::new(vec);
+    Vec::from_raw_parts(vec.as_mut_ptr() as *mut U, vec.len(), vec.capacity())
+}
+
+/// Views a slice of values as their underlying bytes.
+///
+/// # Safety
+///
+/// `T` must not contain any padding bytes.
+unsafe fn as_bytes<T>(slice: &[T]) -> &[u8] {
+    slice::from_raw_parts(slice.as_ptr() as *const u8, mem::size_of_val(slice))
+}
+
+/// Views a mutable slice of values as their underlying bytes.
+///
+/// # Safety
+///
+/// `T` must not contain any padding bytes, and any combination of bytes must be a valid `T`.
+unsafe fn as_bytes_mut<T>(slice: &mut [T]) -> &mut [u8] {
+    slice::from_raw_parts_mut(slice.as_mut_ptr() as *mut u8, mem::size_of_val(slice))
+}
+
+/// A primitive whose sequences can be read and written all at once.
+pub(crate) trait Primitive: Copy + 'static {
+    fn encode_seq<W>(slice: &[Self], endian: Endian, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write;
+
+    fn decode_seq<R>(endian: Endian, len: usize, reader: &mut R) -> Result<Vec<Self>, Error>
+    where
+        R: io::Read;
+
+    fn decode_array<R, const N: usize>(endian: Endian, reader: &mut R) -> Result<[Self; N], Error>
+    where
+        R: io::Read;
+}
+
+macro_rules! impl_primitive {
+    ($($t:ty)*) => {$(
+        impl Primitive for $t {
+            fn encode_seq<W>(slice: &[Self], endian: Endian, writer: &mut W) -> Result<(), Error>
+            where
+                W: io::Write,
+            {
+                if endian == Endian::native() {
+                    // SAFETY: Primitives have no padding bytes.
+                    writer.write_all(unsafe { as_bytes(slice) })?;
+                } else {
+                    // Swap in chunks, so that the copy doesn't grow with the slice.
+                    let chunk_len = limits::PREALLOC_BYTES / mem::size_of::<$t>();
+                    let mut bytes =
+                        Vec::with_capacity(mem::size_of_val(slice).min(limits::PREALLOC_BYTES));
+                    for chunk in slice.chunks(chunk_len) {
+                        bytes.clear();
+                        for elem in chunk {
+                            let mut elem_bytes = elem.to_be_bytes();
+                            from_big_endian(&mut elem_bytes, endian);
+                            bytes.extend_from_slice(&elem_bytes);
+                        }
+                        writer.write_all(&bytes)?;
+                    }
+                }
+                Ok(())
+            }
+
+            fn decode_seq<R>(endian: Endian, len: usize, reader: &mut R) -> Result<Vec<Self>, Error>
+            where
+                R: io::Read,
+            {
+                // Read in chunks, so that the length is only trusted as far as the input goes.
+                let mut acc: Vec<Self> = limits::vec_for_len(len)?;
+                while acc.len() < len {
+                    let start = acc.len();
+                    let chunk_len =
+                        (len - start).min(limits::PREALLOC_BYTES / mem::size_of::<$t>());
+                    limits::alloc(chunk_len * mem::size_of::<$t>())?;
+                    acc.resize(start + chunk_len, 0 as $t);
+                    let chunk = &mut acc[start..];
+                    // SAFETY: Primitives have no padding bytes, and any bytes are a valid value.
+                    reader.read_exact(unsafe { as_bytes_mut(chunk) })?;
+                    for elem in chunk {
+                        let mut bytes = elem.to_ne_bytes();
+                        to_big_endian(&mut bytes, endian);
+                        *elem = Self::from_be_bytes(bytes);
+                    }
+                }
+                Ok(acc)
+            }
+
+            fn decode_array<R, const N: usize>(
+                endian: Endian,
+                reader: &mut R,
+            ) -> Result<[Self; N], Error>
+            where
+                R: io::Read,
+            {
+                let mut arr = [0 as $t; N];
+                // SAFETY: Primitives have no padding bytes, and any bytes are a valid value.
+                reader.read_exact(unsafe { as_bytes_mut(&mut arr) })?;
+                for elem in &mut arr {
+                    let mut bytes = elem.to_ne_bytes();
+                    to_big_endian(&mut bytes, endian);
+                    *elem = Self::from_be_bytes(bytes);
+                }
+                Ok(arr)
+            }
+        }
+    )*}
+}
+
+impl_primitive! {
+    u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64
+}
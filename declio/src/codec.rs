@@ -0,0 +1,257 @@
+//! A [`tokio_util`] codec for framing streams of `declio` messages.
+//!
+//! This module is only available with the `codec` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use bytes::BytesMut;
+//! use declio::codec::{DeclioCodec, LengthPrefix};
+//! use declio::ctx::Endian;
+//! use declio::{Decode, Encode};
+//! use tokio_util::codec::{Decoder, Encoder};
+//!
+//! #[derive(Debug, PartialEq, Encode, Decode)]
+//! #[declio(endian = "big")]
+//! struct Ping {
+//!     seq: u32,
+//! }
+//!
+//! let mut codec = DeclioCodec::<Ping>::new(()).length_prefix(LengthPrefix::U16(Endian::Big));
+//!
+//! let mut buf = BytesMut::new();
+//! codec.encode(Ping { seq: 7 }, &mut buf).unwrap();
+//! assert_eq!(buf[..], [0x00, 0x04, 0x00, 0x00, 0x00, 0x07]);
+//!
+//! assert_eq!(codec.decode(&mut buf).unwrap(), Some(Ping { seq: 7 }));
+//! assert!(buf.is_empty());
+//! ```
+//!
+//! With a [`Framed`](tokio_util::codec::Framed) stream, the codec turns a TCP connection into a
+//! `Stream` and `Sink` of messages.
+
+use crate::ctx::Endian;
+use crate::{Decode, Encode, Error, Partial};
+use bytes::{Buf, BufMut, BytesMut};
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use tokio_util::codec::{Decoder, Encoder};
+
+/// The default value of [`DeclioCodec::max_frame_len`], 8 MiB.
+pub const DEFAULT_MAX_FRAME_LEN: usize = 8 << 20;
+
+/// An integer prefix that holds the length of each frame in bytes, not including the prefix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthPrefix {
+    /// A one-byte length.
+    U8,
+    /// A two-byte length.
+    U16(Endian),
+    /// A four-byte length.
+    U32(Endian),
+    /// An eight-byte length.
+    U64(Endian),
+}
+
+impl LengthPrefix {
+    /// The size of the prefix in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            LengthPrefix::U8 => 1,
+            LengthPrefix::U16(_) => 2,
+            LengthPrefix::U32(_) => 4,
+            LengthPrefix::U64(_) => 8,
+        }
+    }
+
+    fn decode(self, mut bytes: &[u8]) -> Result<usize, Error> {
+        let len = match self {
+            LengthPrefix::U8 => u64::from(u8::decode((), &mut bytes)?),
+            LengthPrefix::U16(endian) => u64::from(u16::decode(endian, &mut bytes)?),
+            LengthPrefix::U32(endian) => u64::from(u32::decode(endian, &mut bytes)?),
+            LengthPrefix::U64(endian) => u64::decode(endian, &mut bytes)?,
+        };
+        Ok(usize::try_from(len)?)
+    }
+
+    fn encode(self, len: usize, mut writer: &mut [u8]) -> Result<(), Error> {
+        let writer = &mut writer;
+        match self {
+            LengthPrefix::U8 => u8::try_from(len)?.encode((), writer),
+            LengthPrefix::U16(endian) => u16::try_from(len)?.encode(endian, writer),
+            LengthPrefix::U32(endian) => u32::try_from(len)?.encode(endian, writer),
+            LengthPrefix::U64(endian) => u64::try_from(len)?.encode(endian, writer),
+        }
+    }
+}
+
+/// A codec that decodes and encodes values of type `T` with the context `Ctx`.
+///
+/// By default, messages are self-delimiting: each one is decoded from the start of the buffered
+/// bytes with [`decode_partial`](crate::decode_partial), and decoding waits for more bytes while
+/// it is incomplete. Since decoding starts over whenever more bytes arrive, this works best for
+/// small messages. Alternatively, frames can have a [`LengthPrefix`], in which case each message
+/// is only decoded once its whole frame has been received, and must use the whole frame.
+///
+/// In both cases, frames longer than [`max_frame_len`](Self::max_frame_len) are rejected.
+///
+/// Self-delimiting messages must not contain anything that is decoded until the end of the stream,
+/// like a [`ToEnd`](crate::ctx::ToEnd) field: it would end wherever the received bytes happen to
+/// end, so a message that arrives in several chunks would be cut short. Such messages need a
+/// length prefix.
+pub struct DeclioCodec<T, Ctx = ()> {
+    ctx: Ctx,
+    length_prefix: Option<LengthPrefix>,
+    max_frame_len: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T, Ctx> DeclioCodec<T, Ctx> {
+    /// Creates a codec for self-delimiting messages, which are decoded and encoded with the given
+    /// context.
+    pub fn new(ctx: Ctx) -> Self {
+        Self {
+            ctx,
+            length_prefix: None,
+            max_frame_len: DEFAULT_MAX_FRAME_LEN,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Frames each message with a length prefix.
+    pub fn length_prefix(mut self, length_prefix: LengthPrefix) -> Self {
+        self.length_prefix = Some(length_prefix);
+        self
+    }
+
+    /// Sets the maximum length of a frame in bytes, not including its length prefix. The default
+    /// is [`DEFAULT_MAX_FRAME_LEN`].
+    pub fn max_frame_len(mut self, max_frame_len: usize) -> Self {
+        self.max_frame_len = max_frame_len;
+        self
+    }
+
+    fn check_len(&self, len: usize) -> Result<(), Error> {
+        if len > self.max_frame_len {
+            return Err(Error::new(format!(
+                "frame of {} bytes exceeds the maximum length of {}",
+                len, self.max_frame_len
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl<T, Ctx> Clone for DeclioCodec<T, Ctx>
+where
+    Ctx: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            ctx: self.ctx.clone(),
+            length_prefix: self.length_prefix,
+            max_frame_len: self.max_frame_len,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, Ctx> fmt::Debug for DeclioCodec<T, Ctx>
+where
+    Ctx: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DeclioCodec")
+            .field("ctx", &self.ctx)
+            .field("length_prefix", &self.length_prefix)
+            .field("max_frame_len", &self.max_frame_len)
+            .finish()
+    }
+}
+
+impl<T, Ctx> Decoder for DeclioCodec<T, Ctx>
+where
+    T: Decode<Ctx>,
+    Ctx: Clone,
+{
+    type Item = T;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, Error> {
+        let length_prefix = match self.length_prefix {
+            Some(length_prefix) => length_prefix,
+            None => {
+                return match crate::decode_partial_with_context(src, self.ctx.clone())? {
+                    Partial::Complete { value, len } => {
+                        src.advance(len);
+                        Ok(Some(value))
+                    }
+                    Partial::Incomplete { needed } => {
+                        self.check_len(src.len() + needed.unwrap_or(1))?;
+                        src.reserve(needed.unwrap_or(0));
+                        Ok(None)
+                    }
+                };
+            }
+        };
+
+        let prefix_size = length_prefix.size();
+        if src.len() < prefix_size {
+            src.reserve(prefix_size - src.len());
+            return Ok(None);
+        }
+        let len = length_prefix.decode(&src[..prefix_size])?;
+        self.check_len(len)?;
+        if src.len() - prefix_size < len {
+            src.reserve(prefix_size + len - src.len());
+            return Ok(None);
+        }
+        src.advance(prefix_size);
+        let frame = src.split_to(len);
+        crate::from_bytes_with_context(&frame, self.ctx.clone()).map(Some)
+    }
+}
+
+impl<T, Ctx> Encoder<T> for DeclioCodec<T, Ctx>
+where
+    T: Encode<Ctx>,
+    Ctx: Clone,
+{
+    type Error = Error;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Error> {
+        Encoder::<&T>::encode(self, &item, dst)
+    }
+}
+
+impl<'a, T, Ctx> Encoder<&'a T> for DeclioCodec<T, Ctx>
+where
+    T: Encode<Ctx>,
+    Ctx: Clone,
+{
+    type Error = Error;
+
+    fn encode(&mut self, item: &'a T, dst: &mut BytesMut) -> Result<(), Error> {
+        // Encode directly after a placeholder for the prefix, then fill it in with the length.
+        let start = dst.len();
+        let prefix_size = self.length_prefix.map_or(0, LengthPrefix::size);
+        dst.put_bytes(0, prefix_size);
+        let result = item
+            .encode(self.ctx.clone(), &mut (&mut *dst).writer())
+            .and_then(|()| {
+                let len = dst.len() - start - prefix_size;
+                self.check_len(len)?;
+                match self.length_prefix {
+                    Some(length_prefix) => {
+                        length_prefix.encode(len, &mut dst[start..][..prefix_size])
+                    }
+                    None => Ok(()),
+                }
+            });
+        if result.is_err() {
+            dst.truncate(start);
+        }
+        result
+    }
+}
@@ -7,6 +7,16 @@ pub enum Endian {
     Big,
     /// Little-endian (least-significant-byte first).
     Little,
+    /// Big-endian bytes within 16-bit words, with the words in little-endian order.
+    ///
+    /// For example, `0x11223344u32` is stored as `[0x33, 0x44, 0x11, 0x22]`. This is the
+    /// "word-swapped" order used by many Modbus devices for 32- and 64-bit values.
+    BigWordSwapped,
+    /// Little-endian bytes within 16-bit words, with the words in big-endian order.
+    ///
+    /// For example, `0x11223344u32` is stored as `[0x22, 0x11, 0x44, 0x33]`. This is the
+    /// "middle-endian" order used by the PDP-11 for 32-bit values.
+    LittleWordSwapped,
 }
 
 impl Endian {
@@ -34,3 +44,72 @@ impl Endian {
 /// The number of elements in variable-sized containers.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Len(pub usize);
+
+/// The number of bytes occupied by variable-sized containers.
+///
+/// When decoding, elements are decoded until all of the bytes have been consumed, and it is an
+/// error if the last element extends past the end. When encoding, it is an error if the elements
+/// do not occupy exactly this many bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ByteLen(pub usize);
+
+/// Decode elements of variable-sized containers until the end of the stream.
+///
+/// Reaching the end of the stream exactly between two elements ends the container, while reaching
+/// it in the middle of an element is an error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToEnd;
+
+/// Decode elements of variable-sized containers until an element is equal to the given
+/// terminator, like the null byte at the end of a C string.
+///
+/// The terminator is consumed when decoding and written when encoding, but it is not part of the
+/// container's contents.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Until<T>(pub T);
+
+/// Pass the same context to every element of a tuple.
+///
+/// Tuples take a tuple of contexts, one per element, like `((), Endian::Little, Endian::Big)` for
+/// `(u8, u16, u32)`. The unit context and an [`Endian`] can also be passed to a tuple as they are,
+/// and are shared by all of its elements; any other cloneable context is shared with this wrapper.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shared<T>(pub T);
+
+/// The binary representation of a `bool`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoolRepr {
+    /// A single byte, either `0` for `false` or `1` for `true`. Any other value is an error when
+    /// decoding.
+    ZeroOne,
+    /// A single byte, `0` for `false` and any other value for `true`. Encodes `true` as `1`.
+    NonZero,
+    /// A single byte, either `0x00` for `false` or `0xff` for `true`. Any other value is an error
+    /// when decoding.
+    ZeroFF,
+    /// An unsigned integer that is `size` bytes wide (between 1 and 8), stored with the given byte
+    /// order. Encodes `false` as `0` and `true` as `1`.
+    ///
+    /// If `strict` is set, decoding any value other than `0` or `1` is an error; otherwise, any
+    /// nonzero value is decoded as `true`.
+    Int {
+        /// The width of the integer in bytes.
+        size: usize,
+        /// The byte order of the integer.
+        endian: Endian,
+        /// Whether to reject values other than `0` and `1`.
+        strict: bool,
+    },
+}
+
+/// The binary representation of a `char`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CharRepr {
+    /// The UTF-8 encoding of the character, which is between 1 and 4 bytes long.
+    Utf8,
+    /// The 4-byte scalar value of the character, stored with the given byte order.
+    Utf32(Endian),
+    /// A single byte, as defined by ISO-8859-1. Characters above `U+00FF` are an error when
+    /// encoding.
+    Latin1,
+}
@@ -10,6 +10,13 @@
 //! `id_type` or `id_expr` must be specified outside the enum, and an `id` expression must be
 //! specified for each variant. See below for more information.
 //!
+//! The `Layout` macro reads the same attributes to describe the binary layout of the type; see the
+//! [`layout`](crate::layout) module for more information.
+//!
+//! Each derived `Decode` implementation enters a [`DepthGuard`](crate::DepthGuard) while decoding
+//! its fields, so the nesting depth of recursive types is bounded by the current
+//! [`Limits`](crate::Limits).
+//!
 //! # Attributes
 //!
 //! The implementation can be modified by attributes at several levels:
@@ -76,6 +83,47 @@
 //! }
 //! ```
 //!
+//! Context fields may also have default values, like `endian: Endian = Endian::Big`. If all of
+//! them do, an additional impl of `Encode<()>` or `Decode<()>` is generated which uses the
+//! defaults, so the type can be used with [`from_bytes`](crate::from_bytes) or as a field
+//! without a `ctx` attribute:
+//!
+//! ```
+//! use declio::{Encode, Decode};
+//! use declio::ctx::Endian;
+//!
+//! #[derive(Encode, Decode)]
+//! #[declio(ctx = "endian: Endian = Endian::Big", endian = "endian")]
+//! struct Version {
+//!     major: u16,
+//!     minor: u16,
+//! }
+//!
+//! let version: Version = declio::from_bytes(&[0, 1, 0, 2]).unwrap();
+//! assert_eq!((version.major, version.minor), (1, 2));
+//! ```
+//!
+//! Instead of a list of context fields, the value may be `"inherit"`, which makes the impl
+//! generic over any context type `Ctx: Clone`. The context value is bound to `ctx`, and a clone
+//! of it is passed to every field that does not have its own `ctx` or `endian` attribute. A
+//! bound like `u16: Encode<Ctx>` is inferred for each of these fields, so they only need to
+//! accept the contexts that the container is actually used with:
+//!
+//! ```
+//! use declio::{Encode, Decode};
+//! use declio::ctx::Endian;
+//!
+//! #[derive(Encode, Decode)]
+//! #[declio(ctx = "inherit")]
+//! struct Point {
+//!     x: i32,
+//!     y: i32,
+//! }
+//!
+//! let bytes = declio::to_bytes_with_context(&Point { x: 1, y: 2 }, Endian::Little).unwrap();
+//! assert_eq!(bytes, [1, 0, 0, 0, 2, 0, 0, 0]);
+//! ```
+//!
 //! - **`id_expr`** (Asymmetric, required for enums, conflicts with `id_type`) - Use the given expression as
 //! the variant ID when decoding. Unlike `id_type`, the variant ID is not encoded or decoded as
 //! part of the enum. Useful for specifying a variant ID via a `ctx` field.  
@@ -90,6 +138,105 @@
 //! - **`id_ctx`** (Asymmetric, conflicts with `id_expr`) - If encoding or decoding a variant ID
 //! with `id_type`, this attribute will set the context used by the ID encoder or decoder.
 //!
+//! - **`endian`** - The default [`Endian`](crate::ctx::Endian) of the fields. It is passed as context
+//! to every field whose type is an integer or floating-point primitive (or an array of them),
+//! unless the field has its own `ctx`, `with`, `encode_with` or `decode_with` attribute. It is
+//! also used for the variant ID, if `id_type` is a primitive and `id_ctx` is not present. The
+//! value is either `"big"`, `"little"`, an expression like the name of a context binding, or
+//! `"ctx"`, which is shorthand for `ctx = "endian: declio::ctx::Endian"`:
+//!
+//! ```
+//! use declio::{Encode, Decode};
+//!
+//! #[derive(Encode, Decode)]
+//! #[declio(endian = "little")]
+//! struct Header {
+//!     version: u16,
+//!     flags: u32,
+//!     // Other types have to opt in with the field-level `endian` attribute.
+//!     #[declio(endian)]
+//!     extension: Extension,
+//! }
+//!
+//! // Accepts the endianness as context.
+//! #[derive(Encode, Decode)]
+//! #[declio(endian = "ctx")]
+//! struct Extension {
+//!     kind: u16,
+//!     length: u16,
+//! }
+//! ```
+//!
+//! - **`endian_from`** (Conflicts with `endian`) - Like `endian`, but the endianness is determined
+//! at runtime from the value of a previous field, like the byte order mark at the start of a
+//! TIFF file. The value is an expression that can be converted into an
+//! [`Endian`](crate::ctx::Endian), and it only applies to the fields after the last one it
+//! refers to. It is not used for the variant ID. The [`endian_marker`](crate::endian_marker)
+//! macro defines types for byte order marks, which also write the matching mark when encoding:
+//!
+//! ```
+//! use declio::{Encode, Decode};
+//! use declio::util::endian_marker;
+//!
+//! endian_marker! {
+//!     ByteOrder { big: b"MM", little: b"II" }
+//! }
+//!
+//! #[derive(Encode, Decode)]
+//! #[declio(endian_from = "byte_order")]
+//! struct TiffHeader {
+//!     byte_order: ByteOrder,
+//!     magic: u16,
+//!     first_ifd_offset: u32,
+//! }
+//!
+//! let header: TiffHeader = declio::from_bytes(b"II\x2a\x00\x08\x00\x00\x00").unwrap();
+//! assert_eq!(header.magic, 42);
+//! assert_eq!(header.first_ifd_offset, 8);
+//! ```
+//!
+//! - **`pod`** (Non-generic structs only) - Encode or decode the entire struct as a single
+//! fixed-size block of bytes, with a single call to `write_all` or `read_exact`. The value is an
+//! expression giving the [`Endian`](crate::ctx::Endian) of every field. All of the fields must be
+//! integer or floating-point primitives, or arrays of them, and they cannot have any attributes;
+//! this is checked at compile time. Fields are laid out in declaration order without padding, so
+//! the struct itself does not need to be `#[repr(C)]`.
+//!
+//! ```
+//! use declio::{Encode, Decode};
+//! use declio::ctx::Endian;
+//!
+//! #[derive(Encode, Decode)]
+//! #[declio(pod = "Endian::Little")]
+//! struct Header {
+//!     magic: [u8; 4],
+//!     version: u16,
+//!     flags: u16,
+//!     length: u32,
+//! }
+//! ```
+//!
+//! - **`bound`** (Asymmetric) - A comma-separated list of where predicates, like `T: Encode`, that
+//! replaces the trait bounds added to the generated impl. By default, a bound is inferred for
+//! each field whose type mentions a type parameter of the container, like `T: Encode<()>` for a
+//! field of type `T`, or `T: Encode<Endian>` if it uses the container's `endian`. Fields with a
+//! `ctx` (other than `"inherit"`), `with`, `encode_with` or `decode_with` attribute don't get an
+//! inferred bound, since the type of their context is not known, so their bounds have to be
+//! given here or in the field-level `bound` attribute. An empty string removes all of the
+//! inferred bounds. If the context is inherited, its type parameter is named `Ctx`.
+//!
+//! ```
+//! use declio::{Encode, Decode};
+//! use declio::ctx::Len;
+//!
+//! #[derive(Encode, Decode)]
+//! #[declio(ctx = "len: usize", bound = "T: Encode + Decode")]
+//! struct Items<T> {
+//!     #[declio(ctx = "Len(len)")]
+//!     items: Vec<T>,
+//! }
+//! ```
+//!
 //! ## Variant Attributes
 //!
 //! - **`id`** - An expression used to match the variant ID when decoding, and to encode the variant
@@ -98,7 +245,14 @@
 //! ## Field Attributes
 //!
 //! - **`ctx`** (Asymmetric) The context value to be passed to the field's encoder or decoder. When
-//! not present, the passed context is the unit context.
+//! not present, the passed context is the unit context, or the container's context if it is
+//! inherited. The value `"inherit"` explicitly passes the container's context, which requires a
+//! container-level `ctx = "inherit"`.
+//!
+//! - **`endian`** (Conflicts with `ctx`) - Passes an [`Endian`](crate::ctx::Endian) as the field's
+//! context. Without a value, like `#[declio(endian)]`, it uses the container-level `endian`, which
+//! is useful for nested types that accept an `Endian` context. Otherwise, it overrides it, with
+//! the same values as the container-level attribute.
 //!
 //! - **`with`** (Conflicts with `encode_with` and `decode_with`) - Uses the given helper functions
 //! to encode or decode the field instead of the field type's `Encode` or `Decode` implementation.
@@ -142,9 +296,9 @@
 //! - **`skip_if`** - If the given expression evaluates true, the field will not be encoded or
 //! decoded. When decoding, the field will be given the value of `Default::default()` instead.
 //!
-//!   For example, this is useful for optionally encoding or decoding a field based on the value of
-//!   a previous field. In particular, it is impossible to get `None` from `Option::decode` without
-//!   using `skip_if`, since it assumes that the inner value is present:
+//! For example, this is useful for optionally encoding or decoding a field based on the value of
+//! a previous field. In particular, it is impossible to get `None` from `Option::decode` without
+//! using `skip_if`, since it assumes that the inner value is present:
 //!
 //! ```
 //! use declio::{Encode, Decode};
@@ -158,3 +312,47 @@
 //!     extra_data: Option<u32>,
 //! }
 //! ```
+//!
+//! - **`byte_len`** - The number of bytes occupied by the field, as a `usize`. When decoding, the
+//! field is decoded from a sub-reader that ends after this many bytes, and it is an error if the
+//! field does not consume all of them. When encoding, it is an error if the encoded field is not
+//! exactly this size. This is useful for sub-structures whose size is given in bytes rather than
+//! by their contents:
+//!
+//! ```
+//! use declio::{Encode, Decode};
+//! use declio::ctx::{Endian, ToEnd};
+//!
+//! #[derive(Encode, Decode)]
+//! struct Chunk {
+//!     #[declio(ctx = "Endian::Big")]
+//!     size: u32,
+//!
+//!     // `ToEnd` decodes elements until the end of the sub-reader.
+//!     #[declio(ctx = "(ToEnd, Endian::Big)", byte_len = "*size as usize")]
+//!     words: Vec<u16>,
+//! }
+//! ```
+//!
+//! - **`byte_len_pad`** (Requires `byte_len`) - Allows the field to be smaller than `byte_len`.
+//! When decoding, any bytes left over after the field are skipped. When encoding, the remainder
+//! is filled with zero bytes.
+//!
+//! - **`bound`** (Asymmetric) - A comma-separated list of where predicates that replaces the bound
+//! inferred for this field; see the container-level `bound` attribute. This is required for
+//! recursive types, where the inferred bound would refer to the container itself:
+//!
+//! ```
+//! use declio::{Encode, Decode};
+//!
+//! #[derive(Encode, Decode)]
+//! struct List<T> {
+//!     value: T,
+//!     has_next: u8,
+//!     #[declio(
+//!         skip_if = "*has_next == 0",
+//!         bound(encode = "T: Encode", decode = "T: Decode"),
+//!     )]
+//!     next: Option<Box<List<T>>>,
+//! }
+//! ```
@@ -2,28 +2,61 @@ use std::fmt;
 
 /// Encoding and decoding errors.
 pub struct Error {
+    kind: ErrorKind,
     message: String,
     source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
 }
 
+/// The general categories of [`Error`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The end of the stream was reached before the value was fully decoded.
+    UnexpectedEof,
+    /// Decoding exceeded one of the resource limits set by [`Limits`](crate::Limits).
+    LimitExceeded,
+    /// Decoding exceeded the maximum nesting depth set by [`Limits`](crate::Limits).
+    DepthExceeded,
+    /// Decoding finished before the end of the input, when the whole input should have been
+    /// consumed.
+    TrailingBytes,
+    /// Any other error.
+    Other,
+}
+
 impl Error {
     /// `Creates a new `Error` with the given message.
     pub fn new<S>(message: S) -> Self
+    where
+        S: ToString,
+    {
+        Self::with_kind(ErrorKind::Other, message)
+    }
+
+    /// Creates a new `Error` of the given kind with the given message.
+    pub fn with_kind<S>(kind: ErrorKind, message: S) -> Self
     where
         S: ToString,
     {
         Self {
+            kind,
             message: message.to_string(),
             source: None,
         }
     }
 
+    /// Creates the error for input that was not consumed entirely.
+    pub(crate) fn trailing_bytes() -> Self {
+        Self::with_kind(ErrorKind::TrailingBytes, "input was not fully consumed")
+    }
+
     /// Creates a new `Error` with the given error value as the source.
     pub fn wrap<E>(error: E) -> Self
     where
         E: std::error::Error + Send + Sync + 'static,
     {
         Self {
+            kind: ErrorKind::Other,
             message: error.to_string(),
             source: Some(Box::new(error)),
         }
@@ -36,10 +69,34 @@ impl Error {
         E: std::error::Error + Send + Sync + 'static,
     {
         Self {
+            kind: ErrorKind::Other,
             message: message.to_string(),
             source: Some(Box::new(error)),
         }
     }
+
+    /// Returns the kind of this error.
+    ///
+    /// Errors that only add context to a source error, like those returned by
+    /// [`with_context`](Self::with_context), have the kind of their source.
+    pub fn kind(&self) -> ErrorKind {
+        let mut error: &(dyn std::error::Error + 'static) = self;
+        loop {
+            if let Some(declio_error) = error.downcast_ref::<Error>() {
+                if declio_error.kind != ErrorKind::Other {
+                    return declio_error.kind;
+                }
+            } else if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+                if io_error.kind() == std::io::ErrorKind::UnexpectedEof {
+                    return ErrorKind::UnexpectedEof;
+                }
+            }
+            match error.source() {
+                Some(source) => error = source,
+                None => return ErrorKind::Other,
+            }
+        }
+    }
 }
 
 impl fmt::Debug for Error {
@@ -0,0 +1,192 @@
+//! Helpers for fuzzing and property testing `Encode` and `Decode` implementations.
+//!
+//! This module is available with the `arbitrary` or `proptest` feature. The round-trip helpers are
+//! available with either of them, and the generators with their respective feature.
+//!
+//! Values of types that use context fields, like a length that must match the length of a later
+//! `Vec`, usually can't be encoded if the fields are generated independently. Instead, the
+//! generators in this module produce values by decoding them from random bytes, so every value
+//! they produce satisfies the `declio` attributes of its type, and can be encoded.
+//!
+//! Decoding in this module is done with [`LIMITS`], so that small inputs can't cause large
+//! allocations.
+//!
+//! # Examples
+//!
+//! A fuzz target for [`cargo fuzz`], checking that everything that decodes also round-trips:
+//!
+//! ```ignore
+//! #![no_main]
+//! use libfuzzer_sys::fuzz_target;
+//!
+//! fuzz_target!(|data: &[u8]| {
+//!     declio::fuzz::roundtrip::<my_crate::Packet>(data);
+//! });
+//! ```
+//!
+//! Generating values with `arbitrary`, as part of a larger input:
+//!
+//! ```ignore
+//! use arbitrary::Arbitrary;
+//! use declio::fuzz::Decoded;
+//!
+//! #[derive(Debug, Arbitrary)]
+//! struct Input {
+//!     packet: Decoded<my_crate::Packet>,
+//!     split_at: usize,
+//! }
+//! ```
+//!
+//! And with `proptest`:
+//!
+//! ```ignore
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn packet_roundtrip(packet in declio::fuzz::strategy::<my_crate::Packet>()) {
+//!         let bytes = declio::to_bytes(&packet).unwrap();
+//!         prop_assert_eq!(declio::from_bytes::<my_crate::Packet>(&bytes).unwrap(), packet);
+//!     }
+//! }
+//! ```
+//!
+//! [`cargo fuzz`]: https://github.com/rust-fuzz/cargo-fuzz
+
+use crate::{Decode, Encode, Limits};
+use std::fmt::Debug;
+
+/// The limits applied while decoding in this module.
+pub const LIMITS: Limits = Limits {
+    max_alloc: 1 << 24,
+    max_len: 1 << 16,
+    max_depth: 64,
+};
+
+/// Decodes a value from a prefix of `bytes`, returning it with the number of bytes it used.
+fn decode_prefix<T, Ctx>(mut bytes: &[u8], ctx: Ctx) -> Option<(T, usize)>
+where
+    T: Decode<Ctx>,
+{
+    let len = bytes.len();
+    let value = LIMITS.scope(|| T::decode(ctx, &mut bytes)).ok()?;
+    Some((value, len - bytes.len()))
+}
+
+/// Checks that decoding `data` and encoding the result round-trips.
+///
+/// Input that fails to decode, or is not consumed entirely, is ignored. Otherwise, the decoded
+/// value is encoded, and the encoded bytes are decoded again, which must give the same value.
+///
+/// The encoded bytes are not compared to `data`, because formats may have several encodings of the
+/// same value, like a [`BoolRepr`](crate::ctx::BoolRepr) that decodes any non-zero byte as `true`.
+///
+/// # Panics
+///
+/// Panics if a decoded value fails to encode, or round-trips to a different value.
+#[track_caller]
+pub fn roundtrip<T>(data: &[u8])
+where
+    T: Encode + Decode + Debug + PartialEq,
+{
+    roundtrip_with_context::<T, ()>(data, ())
+}
+
+/// Checks that decoding `data` and encoding the result round-trips, with context.
+///
+/// See [`roundtrip`] for details.
+#[track_caller]
+pub fn roundtrip_with_context<T, Ctx>(data: &[u8], ctx: Ctx)
+where
+    T: Encode<Ctx> + Decode<Ctx> + Debug + PartialEq,
+    Ctx: Clone,
+{
+    let value = match decode_prefix::<T, Ctx>(data, ctx.clone()) {
+        Some((value, len)) if len == data.len() => value,
+        _ => return,
+    };
+    let bytes = match crate::to_bytes_with_context(&value, ctx.clone()) {
+        Ok(bytes) => bytes,
+        Err(error) => panic!("decoded value {:?} failed to encode: {}", value, error),
+    };
+    match LIMITS.scope(|| crate::from_bytes_with_context::<T, Ctx>(&bytes, ctx)) {
+        Ok(decoded) => assert_eq!(
+            decoded, value,
+            "re-decoding the encoded value gave a different value"
+        ),
+        Err(error) => panic!(
+            "encoded value {:?} failed to decode from {:?}: {}",
+            value, bytes, error
+        ),
+    }
+}
+
+/// A value generated by decoding it from the fuzzer's input.
+///
+/// It implements [`Arbitrary`](arbitrary::Arbitrary) for any type that can be decoded without
+/// context. Types that need context can implement it with [`arbitrary_with_context`] instead.
+#[cfg(feature = "arbitrary")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded<T>(pub T);
+
+#[cfg(feature = "arbitrary")]
+impl<'a, T> arbitrary::Arbitrary<'a> for Decoded<T>
+where
+    T: Decode,
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_with_context(u, ()).map(Decoded)
+    }
+}
+
+/// Generates a value by decoding it from the start of the unstructured data, with context.
+///
+/// Only the bytes used by the decoder are consumed. If decoding fails, this returns
+/// [`IncorrectFormat`](arbitrary::Error::IncorrectFormat), which fuzzers treat as an
+/// uninteresting input.
+#[cfg(feature = "arbitrary")]
+pub fn arbitrary_with_context<T, Ctx>(
+    u: &mut arbitrary::Unstructured,
+    ctx: Ctx,
+) -> arbitrary::Result<T>
+where
+    T: Decode<Ctx>,
+{
+    let (value, len) = decode_prefix(u.peek_bytes(u.len()).unwrap_or_default(), ctx)
+        .ok_or(arbitrary::Error::IncorrectFormat)?;
+    u.bytes(len)?;
+    Ok(value)
+}
+
+/// A strategy that generates values by decoding them from random bytes.
+#[cfg(feature = "proptest")]
+pub fn strategy<T>() -> impl proptest::strategy::Strategy<Value = T>
+where
+    T: Decode + Debug,
+{
+    strategy_with_context(())
+}
+
+/// A strategy that generates values by decoding them from random bytes, with context.
+///
+/// Byte strings are generated with up to 1024 bytes, and those that fail to decode are rejected.
+/// Bytes are biased towards zero and small values, so that length prefixes more often fit in the
+/// generated input. Values shrink along with the bytes they are decoded from.
+#[cfg(feature = "proptest")]
+pub fn strategy_with_context<T, Ctx>(ctx: Ctx) -> impl proptest::strategy::Strategy<Value = T>
+where
+    T: Decode<Ctx> + Debug,
+    Ctx: Clone + Debug,
+{
+    use proptest::strategy::{Just, Strategy};
+
+    let byte = proptest::prop_oneof![
+        2 => Just(0),
+        1 => 1..16u8,
+        1 => proptest::num::u8::ANY,
+    ];
+    proptest::collection::vec(byte, 0..=1024)
+        .prop_filter_map("bytes failed to decode", move |bytes| {
+            decode_prefix(&bytes, ctx.clone()).map(|(value, _)| value)
+        })
+}
@@ -0,0 +1,115 @@
+use crate::{Decode, Encode, Error};
+use std::io;
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
+/// Returns an iterator that decodes consecutive values of type `T` from a reader, each with the
+/// same context.
+///
+/// This is meant for streams that are a sequence of records, like log files or packet captures.
+/// Reaching the end of the stream between two records ends the iterator, but reaching it in the
+/// middle of a record yields an error of kind [`UnexpectedEof`](crate::ErrorKind::UnexpectedEof).
+/// The iterator also ends after yielding any error, since the position of the next record is not
+/// known.
+///
+/// Reading one byte at a time can be slow, so consider wrapping the reader in an
+/// [`io::BufReader`].
+///
+/// # Example
+///
+/// ```
+/// use declio::ctx::Endian;
+///
+/// let bytes = [0x00, 0x01, 0x00, 0x02, 0x00];
+/// let mut records = declio::decode_iter::<u16, _, _>(&bytes[..], Endian::Big);
+///
+/// assert_eq!(records.next().unwrap().unwrap(), 1);
+/// assert_eq!(records.next().unwrap().unwrap(), 2);
+/// assert!(records.next().unwrap().is_err()); // truncated record
+/// assert!(records.next().is_none());
+/// ```
+pub fn decode_iter<T, R, Ctx>(reader: R, ctx: Ctx) -> DecodeIter<T, R, Ctx>
+where
+    T: Decode<Ctx>,
+    R: io::Read,
+    Ctx: Clone,
+{
+    DecodeIter {
+        reader,
+        ctx,
+        done: false,
+        _marker: PhantomData,
+    }
+}
+
+/// Encodes each value of an iterator to a writer in order, with the same context.
+///
+/// This is the counterpart of [`decode_iter`].
+pub fn encode_iter<I, W, Ctx>(writer: &mut W, values: I, ctx: Ctx) -> Result<(), Error>
+where
+    I: IntoIterator,
+    I::Item: Encode<Ctx>,
+    W: io::Write,
+    Ctx: Clone,
+{
+    for value in values {
+        value.encode(ctx.clone(), writer)?;
+    }
+    Ok(())
+}
+
+/// An iterator that decodes consecutive values from a reader.
+///
+/// This is created by [`decode_iter`].
+#[derive(Debug)]
+pub struct DecodeIter<T, R, Ctx> {
+    reader: R,
+    ctx: Ctx,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T, R, Ctx> DecodeIter<T, R, Ctx> {
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Consumes the iterator, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<T, R, Ctx> Iterator for DecodeIter<T, R, Ctx>
+where
+    T: Decode<Ctx>,
+    R: io::Read,
+    Ctx: Clone,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = crate::decode_next(self.ctx.clone(), &mut self.reader).transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+}
+
+impl<T, R, Ctx> FusedIterator for DecodeIter<T, R, Ctx>
+where
+    T: Decode<Ctx>,
+    R: io::Read,
+    Ctx: Clone,
+{
+}
@@ -0,0 +1,245 @@
+//! Introspection of the binary layout of derived types.
+//!
+//! The [`Layout`] trait describes how a type is encoded, as a runtime data structure. It can be
+//! implemented with a derive macro, which reads the same `declio` attributes as the `Encode` and
+//! `Decode` macros, so the description always matches the implementation. This is useful for
+//! generating protocol documentation, or for building debugging tools.
+//!
+//! Attribute expressions like contexts and skip conditions are kept as source text, since they
+//! can only be evaluated while encoding or decoding.
+//!
+//! # Example
+//!
+//! ```
+//! use declio::{Encode, Decode, Layout};
+//! use declio::ctx::Len;
+//! use std::convert::TryInto;
+//!
+//! #[derive(Encode, Decode, Layout)]
+//! #[declio(endian = "big")]
+//! struct Message {
+//!     kind: u8,
+//!     len: u16,
+//!     #[declio(ctx = "Len((*len).try_into()?)")]
+//!     payload: Vec<u8>,
+//! }
+//!
+//! let layout = Message::layout();
+//! assert_eq!(layout.variants[0].fields[1].size, Some(2));
+//! assert_eq!(layout.size(), None);
+//!
+//! print!("{}", layout.to_markdown());
+//! ```
+//!
+//! This prints the following document:
+//!
+//! ```text
+//! ## `Message`
+//!
+//! | Field | Type | Offset | Size | Context | Notes |
+//! | --- | --- | --- | --- | --- | --- |
+//! | `kind` | `u8` | 0 | 1 | `Endian::Big` |  |
+//! | `len` | `u16` | 1 | 2 | `Endian::Big` |  |
+//! | `payload` | `Vec<u8>` | 3 |  | `Len((*len).try_into()?)` |  |
+//! ```
+
+use std::fmt::Write;
+
+/// Types that can describe their binary layout.
+///
+/// This should be implemented with the derive macro of the same name; see the
+/// [module documentation](self) for more information.
+pub trait Layout {
+    /// Returns a description of the binary layout of this type.
+    fn layout() -> ContainerLayout;
+}
+
+/// The layout of a struct or enum.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerLayout {
+    /// The name of the type.
+    pub name: &'static str,
+    /// Whether the type is a struct or an enum.
+    pub kind: ContainerKind,
+    /// The context fields accepted by `Encode`, as given by the `ctx` attribute.
+    pub encode_ctx: Option<&'static str>,
+    /// The context fields accepted by `Decode`, as given by the `ctx` attribute.
+    pub decode_ctx: Option<&'static str>,
+    /// The type of the variant ID, as given by the `id_type` attribute.
+    pub id_type: Option<&'static str>,
+    /// The size of the variant ID in bytes, if it is known statically.
+    pub id_size: Option<usize>,
+    /// The variants of an enum, or a single unnamed variant for a struct.
+    pub variants: Vec<VariantLayout>,
+}
+
+/// The kind of a [`ContainerLayout`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContainerKind {
+    /// A struct.
+    Struct,
+    /// An enum.
+    Enum,
+}
+
+/// The layout of an enum variant, or of the fields of a struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantLayout {
+    /// The name of the variant, or `None` for structs.
+    pub name: Option<&'static str>,
+    /// The variant ID, as given by the `id` attribute.
+    pub id: Option<&'static str>,
+    /// The fields of the variant, in the order they are encoded.
+    pub fields: Vec<FieldLayout>,
+}
+
+/// The layout of a single field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldLayout {
+    /// The name of the field, or its index in a tuple struct or variant.
+    pub name: &'static str,
+    /// The type of the field.
+    pub ty: &'static str,
+    /// The context passed to the field's encoder, if it is not the unit context.
+    pub encode_ctx: Option<&'static str>,
+    /// The context passed to the field's decoder, if it is not the unit context.
+    pub decode_ctx: Option<&'static str>,
+    /// The function used to encode or decode the field, from a `with`, `encode_with` or
+    /// `decode_with` attribute.
+    pub with: Option<&'static str>,
+    /// The condition under which the field is skipped, as given by the `skip_if` attribute.
+    pub skip_if: Option<&'static str>,
+    /// The number of bytes occupied by the field, as given by the `byte_len` attribute.
+    pub byte_len: Option<&'static str>,
+    /// The size of the field in bytes, if it is known statically. This is never known for fields
+    /// that may be skipped.
+    pub size: Option<usize>,
+}
+
+impl ContainerLayout {
+    /// The size of the type in bytes, if it is known statically.
+    ///
+    /// The size of an enum is only known if its variant ID and all of its variants have a known
+    /// size, and the variants are all the same size.
+    pub fn size(&self) -> Option<usize> {
+        let mut sizes = self.variants.iter().map(VariantLayout::size);
+        let first = sizes.next().unwrap_or(Some(0))?;
+        if !sizes.all(|size| size == Some(first)) {
+            return None;
+        }
+        match (self.kind, self.id_type) {
+            (ContainerKind::Enum, Some(_)) => self.id_size?.checked_add(first),
+            _ => Some(first),
+        }
+    }
+
+    /// Renders the layout as a markdown document, with a table of fields for each variant.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "## `{}`", self.name).unwrap();
+        writeln!(out).unwrap();
+
+        let mut props = Vec::new();
+        if let Some(ctx) = ctx_text(self.encode_ctx, self.decode_ctx) {
+            props.push(format!("Context: {}", ctx));
+        }
+        if let Some(id_type) = self.id_type {
+            props.push(format!("ID type: `{}`", escape(id_type)));
+        }
+        if let Some(size) = self.size() {
+            props.push(format!("Size: {} bytes", size));
+        }
+        if !props.is_empty() {
+            for prop in props {
+                writeln!(out, "- {}", prop).unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+
+        for (index, variant) in self.variants.iter().enumerate() {
+            if index > 0 {
+                writeln!(out).unwrap();
+            }
+            if let Some(name) = variant.name {
+                match variant.id {
+                    Some(id) => writeln!(out, "### `{}` (ID `{}`)", name, escape(id)).unwrap(),
+                    None => writeln!(out, "### `{}`", name).unwrap(),
+                }
+                writeln!(out).unwrap();
+            }
+            variant.write_markdown_table(&mut out);
+        }
+        out
+    }
+}
+
+impl VariantLayout {
+    /// The total size of the fields in bytes, if it is known statically.
+    pub fn size(&self) -> Option<usize> {
+        self.fields
+            .iter()
+            .try_fold(0usize, |acc, field| acc.checked_add(field.size?))
+    }
+
+    fn write_markdown_table(&self, out: &mut String) {
+        if self.fields.is_empty() {
+            writeln!(out, "No fields.").unwrap();
+            return;
+        }
+        writeln!(out, "| Field | Type | Offset | Size | Context | Notes |").unwrap();
+        writeln!(out, "| --- | --- | --- | --- | --- | --- |").unwrap();
+
+        // Offsets are known until the first field with an unknown size.
+        let mut offset = Some(0usize);
+        for field in &self.fields {
+            let mut notes = Vec::new();
+            if let Some(with) = field.with {
+                notes.push(format!("with `{}`", escape(with)));
+            }
+            if let Some(byte_len) = field.byte_len {
+                notes.push(format!("`{}` bytes", escape(byte_len)));
+            }
+            if let Some(skip_if) = field.skip_if {
+                notes.push(format!("skipped if `{}`", escape(skip_if)));
+            }
+
+            writeln!(
+                out,
+                "| `{}` | `{}` | {} | {} | {} | {} |",
+                field.name,
+                escape(field.ty),
+                display_option(offset),
+                display_option(field.size),
+                ctx_text(field.encode_ctx, field.decode_ctx).unwrap_or_default(),
+                notes.join(", "),
+            )
+            .unwrap();
+
+            offset = offset
+                .zip(field.size)
+                .and_then(|(offset, size)| offset.checked_add(size));
+        }
+    }
+}
+
+/// Formats a pair of encode and decode contexts, only showing both if they are different.
+fn ctx_text(encode: Option<&str>, decode: Option<&str>) -> Option<String> {
+    match (encode, decode) {
+        (None, None) => None,
+        (Some(encode), Some(decode)) if encode == decode => Some(format!("`{}`", escape(encode))),
+        (encode, decode) => Some(format!(
+            "`{}` (encode), `{}` (decode)",
+            escape(encode.unwrap_or("()")),
+            escape(decode.unwrap_or("()")),
+        )),
+    }
+}
+
+fn display_option(value: Option<usize>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// Escapes characters that would end a markdown table cell.
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
@@ -8,16 +8,31 @@
 //! and collections and other container types encode and decode the data they contain.
 //!
 //! However, there are some notable exceptions; for example, there are no implementations for
-//! `bool` and `str`/`String`. This is because these types have several common representations, so
-//! to avoid accidental misuse, you are required to explicitly declare their representation. Some
-//! of the common representations are provided in the [`util`] module, implemented as both wrapper
-//! types and helper modules.
+//! `str`/`String`, and `bool` and `char` can only be used with a [`BoolRepr`] or [`CharRepr`] context.
+//! This is because these types have several common representations, so to avoid accidental misuse,
+//! you are required to explicitly declare their representation. Some of the common representations
+//! are provided in the [`util`] module, implemented as both wrapper types and helper modules.
 //!
 //! This crate also provides a pair of derive macros, via the default feature `derive`, that can
 //! implement `Encode` and `Decode` for arbitrary compound data types. By default it will encode
 //! and decode all of its fields in order, but it is highly configurable, intended to target the
 //! many different patterns found in binary formats.
 //!
+//! The default feature `bulk` reads and writes sequences and arrays of integer and floating-point
+//! primitives all at once, instead of one element at a time. It adds a dependency on `typeid`.
+//!
+//! The optional `testing` feature adds the `testing` module, with assertions for checking that types
+//! round-trip through their binary representation.
+//!
+//! The optional `arbitrary` and `proptest` features add the `fuzz` module, which generates values
+//! for fuzzing and property tests that are consistent with their `declio` attributes.
+//!
+//! The optional `codec` feature adds the `codec` module, with a `tokio_util` codec for framing
+//! streams of messages.
+//!
+//! The optional `bytes` feature adds functions for decoding from and encoding to the buffer types of
+//! the `bytes` crate, and support for `Bytes` fields that share memory with the decoded input.
+//!
 //! Inspiration for this crate largely comes from [`deku`], but incorporating some changes based on
 //! my own opinions and preferences. For example, `declio` uses byte-wise data streams from
 //! `std::io` instead of the bit-wise `BitVec`s used by `deku`.
@@ -42,7 +57,9 @@
 //! wrapper types or helper functions. In this case, it tells the implementation of `Encode` for
 //! `u32` to encode the bytes in big-endian order. It can instead be set to [`Endian::Little`] to
 //! reverse the byte order, and in general, [`Endian`] can be passed to any of the integer and
-//! floating-point primitive types for similar effects.
+//! floating-point primitive types for similar effects. There are also word-swapped orders like
+//! [`Endian::BigWordSwapped`], for formats that store multi-byte values as a sequence of 16-bit
+//! words.
 //!
 //! Context can also be used to abstract some fields that are necessary to encode and decode some
 //! types. For example, containers with variable length like `Vec` accept a [`Len`] context value
@@ -135,18 +152,82 @@
 
 #![warn(missing_docs)]
 
+#[cfg(feature = "bytes")]
+mod buf;
+#[cfg(feature = "bulk")]
+mod bulk;
 mod error;
+mod iter;
+mod limits;
 mod macros;
+mod partial;
+mod pod;
+mod seq;
 
+#[cfg(feature = "codec")]
+pub mod codec;
 pub mod ctx;
+// The attribute reference continues list items without indenting them.
+#[allow(clippy::doc_lazy_continuation)]
 pub mod derive;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+pub mod fuzz;
+pub mod layout;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod trace;
 pub mod util;
 
-pub use self::error::Error;
+#[cfg(feature = "bytes")]
+pub use self::buf::{decode_buf, decode_bytes, encode_buf};
+pub use self::error::{Error, ErrorKind};
+pub use self::iter::{decode_iter, encode_iter, DecodeIter};
+pub use self::layout::Layout;
+pub use self::limits::{DepthGuard, Limits};
+pub use self::partial::{decode_partial, decode_partial_with_context, Partial};
 
+#[doc(hidden)]
+pub use self::pod::Pod;
 #[doc(hidden)]
 pub use std as export;
 
+/// Functions used by the derive macros.
+#[doc(hidden)]
+pub mod __private {
+    use crate::{Decode, Encode, Error};
+    use std::io;
+    use std::marker::PhantomData;
+
+    /// Encodes a value with the given context.
+    ///
+    /// The context comes first, so that its type is inferred before the `Encode` bound is
+    /// resolved. Otherwise, the bound may be resolved by a where clause of the derived impl with a
+    /// different context type.
+    pub fn encode<Ctx, T, W>(ctx: Ctx, value: &T, writer: &mut W) -> Result<(), Error>
+    where
+        T: Encode<Ctx> + ?Sized,
+        W: io::Write,
+    {
+        value.encode(ctx, writer)
+    }
+
+    /// Decodes a value of the type given by the marker with the given context.
+    ///
+    /// Like [`encode`], the context comes before the type of the value.
+    pub fn decode<Ctx, T, R>(ctx: Ctx, _ty: PhantomData<T>, reader: &mut R) -> Result<T, Error>
+    where
+        T: Decode<Ctx>,
+        R: io::Read,
+    {
+        T::decode(ctx, reader)
+    }
+
+    /// Hooks for recording a [`Trace`](crate::trace::Trace).
+    pub mod trace {
+        pub use crate::trace::{active, DebugValue, SpanGuard, SpanKind, TraceDebug, TraceNoDebug};
+    }
+}
+
 #[cfg(feature = "derive")]
 /// Implements [`Decode`] for a given type. For more information, see [`derive`](derive/index.html).
 pub use declio_derive::Decode;
@@ -155,9 +236,15 @@ pub use declio_derive::Decode;
 /// Implements [`Encode`] for a given type. For more information, see [`derive`](derive/index.html).
 pub use declio_derive::Encode;
 
-use self::ctx::{Endian, Len};
+#[cfg(feature = "derive")]
+/// Implements [`Layout`] for a given type. For more information, see [`layout`](layout/index.html).
+pub use declio_derive::Layout;
+
+use self::ctx::{BoolRepr, ByteLen, CharRepr, Endian, Len, Shared, ToEnd, Until};
 use std::borrow::Cow;
-use std::{io, mem};
+use std::convert::TryFrom;
+use std::mem::MaybeUninit;
+use std::{io, mem, ptr};
 
 /// Encodes a value into a vector of bytes.
 pub fn to_bytes<T>(value: T) -> Result<Vec<u8>, Error>
@@ -177,10 +264,53 @@ where
     Ok(bytes)
 }
 
+/// Encodes a value into a byte slice, returning the number of bytes written.
+///
+/// If the value does not fit in the slice, this returns an error, and the slice may have been
+/// partially written.
+pub fn to_slice<T>(value: T, buf: &mut [u8]) -> Result<usize, Error>
+where
+    T: Encode,
+{
+    to_slice_with_context(value, (), buf)
+}
+
+/// Encodes a value into a byte slice, with context, returning the number of bytes written.
+///
+/// If the value does not fit in the slice, this returns an error, and the slice may have been
+/// partially written.
+pub fn to_slice_with_context<T, Ctx>(value: T, ctx: Ctx, buf: &mut [u8]) -> Result<usize, Error>
+where
+    T: Encode<Ctx>,
+{
+    let len = buf.len();
+    let mut remaining = buf;
+    value.encode(ctx, &mut remaining)?;
+    Ok(len - remaining.len())
+}
+
+/// Encodes a value into a writer.
+pub fn to_writer<T, W>(value: T, writer: W) -> Result<(), Error>
+where
+    T: Encode,
+    W: io::Write,
+{
+    to_writer_with_context(value, (), writer)
+}
+
+/// Encodes a value into a writer, with context.
+pub fn to_writer_with_context<T, Ctx, W>(value: T, ctx: Ctx, mut writer: W) -> Result<(), Error>
+where
+    T: Encode<Ctx>,
+    W: io::Write,
+{
+    value.encode(ctx, &mut writer)
+}
+
 /// Decodes a value from a byte slice.
 ///
 /// The byte slice should be consumed entirely; if there are bytes left over after decoding, it
-/// will return an error.
+/// will return an error of kind [`TrailingBytes`](ErrorKind::TrailingBytes).
 pub fn from_bytes<T>(bytes: &[u8]) -> Result<T, Error>
 where
     T: Decode,
@@ -191,19 +321,109 @@ where
 /// Decodes a value from a byte slice, with context.
 ///
 /// The byte slice should be consumed entirely; if there are bytes left over after decoding, it
-/// will return an error.
-pub fn from_bytes_with_context<T, Ctx>(mut bytes: &[u8], ctx: Ctx) -> Result<T, Error>
+/// will return an error of kind [`TrailingBytes`](ErrorKind::TrailingBytes).
+pub fn from_bytes_with_context<T, Ctx>(bytes: &[u8], ctx: Ctx) -> Result<T, Error>
 where
     T: Decode<Ctx>,
 {
-    let value = T::decode(ctx, &mut bytes)?;
-    if bytes.is_empty() {
+    let (value, rest) = from_bytes_prefix_with_context(bytes, ctx)?;
+    if rest.is_empty() {
         Ok(value)
     } else {
-        Err(Error::new("byte slice was not fully consumed"))
+        Err(Error::trailing_bytes())
+    }
+}
+
+/// Decodes a value from the start of a byte slice, returning it with the rest of the slice.
+pub fn from_bytes_prefix<T>(bytes: &[u8]) -> Result<(T, &[u8]), Error>
+where
+    T: Decode,
+{
+    from_bytes_prefix_with_context(bytes, ())
+}
+
+/// Decodes a value from the start of a byte slice, with context, returning it with the rest of the
+/// slice.
+pub fn from_bytes_prefix_with_context<T, Ctx>(
+    mut bytes: &[u8],
+    ctx: Ctx,
+) -> Result<(T, &[u8]), Error>
+where
+    T: Decode<Ctx>,
+{
+    let value = T::decode(ctx, &mut bytes)?;
+    Ok((value, bytes))
+}
+
+/// Decodes a value from a reader.
+///
+/// Only the bytes of the value are read, and the rest of the stream is left in the reader.
+pub fn from_reader<T, R>(reader: R) -> Result<T, Error>
+where
+    T: Decode,
+    R: io::Read,
+{
+    from_reader_with_context(reader, ())
+}
+
+/// Decodes a value from a reader, with context.
+///
+/// Only the bytes of the value are read, and the rest of the stream is left in the reader.
+pub fn from_reader_with_context<T, Ctx, R>(mut reader: R, ctx: Ctx) -> Result<T, Error>
+where
+    T: Decode<Ctx>,
+    R: io::Read,
+{
+    T::decode(ctx, &mut reader)
+}
+
+/// Decodes a value from a reader, which should be at the end of the stream afterwards.
+///
+/// If the stream does not end after the value, this returns an error of kind
+/// [`TrailingBytes`](ErrorKind::TrailingBytes). Checking for the end reads one more byte, which is
+/// lost if there is one.
+pub fn from_reader_to_end<T, R>(reader: R) -> Result<T, Error>
+where
+    T: Decode,
+    R: io::Read,
+{
+    from_reader_to_end_with_context(reader, ())
+}
+
+/// Decodes a value from a reader, with context, which should be at the end of the stream
+/// afterwards.
+///
+/// If the stream does not end after the value, this returns an error of kind
+/// [`TrailingBytes`](ErrorKind::TrailingBytes). Checking for the end reads one more byte, which is
+/// lost if there is one.
+pub fn from_reader_to_end_with_context<T, Ctx, R>(mut reader: R, ctx: Ctx) -> Result<T, Error>
+where
+    T: Decode<Ctx>,
+    R: io::Read,
+{
+    let value = T::decode(ctx, &mut reader)?;
+    let mut byte = [0u8];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) => return Ok(value),
+            Ok(_) => return Err(Error::trailing_bytes()),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        }
     }
 }
 
+/// Decodes a value from a byte slice, with context, applying the given limits.
+///
+/// The byte slice should be consumed entirely; if there are bytes left over after decoding, it
+/// will return an error of kind [`TrailingBytes`](ErrorKind::TrailingBytes).
+pub fn from_bytes_with_limits<T, Ctx>(bytes: &[u8], ctx: Ctx, limits: Limits) -> Result<T, Error>
+where
+    T: Decode<Ctx>,
+{
+    limits.scope(|| from_bytes_with_context(bytes, ctx))
+}
+
 /// A type that can be encoded into a byte stream.
 pub trait Encode<Ctx = ()> {
     /// Encodes `&self` to the given writer.
@@ -289,10 +509,120 @@ where
     where
         W: io::Write,
     {
-        for elem in self {
-            elem.encode(inner_ctx.clone(), writer)?;
+        seq::encode_seq(self, inner_ctx, writer)
+    }
+}
+
+impl<T, Ctx> Encode<(ByteLen, Ctx)> for [T]
+where
+    T: Encode<Ctx>,
+    Ctx: Clone,
+{
+    /// Encodes each element of the slice in order.
+    ///
+    /// If the byte length is also to be encoded, it has to be done separately.
+    ///
+    /// The byte length context is checked against the encoded size of the elements; if they are
+    /// not equal, then this function will return an error.
+    fn encode<W>(
+        &self,
+        (ByteLen(len), inner_ctx): (ByteLen, Ctx),
+        writer: &mut W,
+    ) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        util::encode_byte_len(len, false, writer, |buf| self.encode((inner_ctx,), buf))
+    }
+}
+
+impl<T> Encode<ByteLen> for [T]
+where
+    T: Encode,
+{
+    /// Encodes each element of the slice in order.
+    ///
+    /// If the byte length is also to be encoded, it has to be done separately.
+    ///
+    /// The byte length context is checked against the encoded size of the elements; if they are
+    /// not equal, then this function will return an error.
+    fn encode<W>(&self, byte_len: ByteLen, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.encode((byte_len, ()), writer)
+    }
+}
+
+impl<T, Ctx> Encode<(ToEnd, Ctx)> for [T]
+where
+    T: Encode<Ctx>,
+    Ctx: Clone,
+{
+    /// Encodes each element of the slice in order.
+    ///
+    /// Nothing else is written; the end of the sequence is marked by the end of the stream.
+    fn encode<W>(&self, (ToEnd, inner_ctx): (ToEnd, Ctx), writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.encode((inner_ctx,), writer)
+    }
+}
+
+impl<T> Encode<ToEnd> for [T]
+where
+    T: Encode,
+{
+    /// Encodes each element of the slice in order.
+    ///
+    /// Nothing else is written; the end of the sequence is marked by the end of the stream.
+    fn encode<W>(&self, to_end: ToEnd, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.encode((to_end, ()), writer)
+    }
+}
+
+impl<T, Ctx> Encode<(Until<T>, Ctx)> for [T]
+where
+    T: Encode<Ctx> + PartialEq,
+    Ctx: Clone,
+{
+    /// Encodes each element of the slice in order, followed by the terminator.
+    ///
+    /// If one of the elements is equal to the terminator, this function will return an error,
+    /// since the sequence would end early when decoding it.
+    fn encode<W>(
+        &self,
+        (Until(terminator), inner_ctx): (Until<T>, Ctx),
+        writer: &mut W,
+    ) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        if self.contains(&terminator) {
+            return Err(Error::new("slice contains the terminator value"));
         }
-        Ok(())
+        self.encode((inner_ctx.clone(),), writer)?;
+        terminator.encode(inner_ctx, writer)
+    }
+}
+
+impl<T> Encode<Until<T>> for [T]
+where
+    T: Encode + PartialEq,
+{
+    /// Encodes each element of the slice in order, followed by the terminator.
+    ///
+    /// If one of the elements is equal to the terminator, this function will return an error,
+    /// since the sequence would end early when decoding it.
+    fn encode<W>(&self, until: Until<T>, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.encode((until, ()), writer)
     }
 }
 
@@ -305,29 +635,57 @@ where
     where
         W: io::Write,
     {
-        for elem in self {
-            elem.encode(inner_ctx.clone(), writer)?;
-        }
-        Ok(())
+        seq::encode_seq(self, inner_ctx, writer)
     }
 }
 
 impl<T, Ctx, const N: usize> Decode<Ctx> for [T; N]
 where
-    T: Decode<Ctx> + Copy + Default,
+    T: Decode<Ctx>,
     Ctx: Clone,
 {
     fn decode<R>(inner_ctx: Ctx, reader: &mut R) -> Result<Self, Error>
     where
         R: io::Read,
     {
-        //TODO: Use MaybeUninit when stabilized with arrays
-        let mut arr = [Default::default(); N];
-        for slot in &mut arr {
-            *slot = Decode::decode(inner_ctx.clone(), reader)?;
+        seq::decode_array(inner_ctx, reader)
+    }
+}
+
+/// Creates an array by calling `f` with each index in order.
+///
+/// If `f` returns an error, the elements that were already created are dropped.
+pub(crate) fn try_array<T, F, const N: usize>(mut f: F) -> Result<[T; N], Error>
+where
+    F: FnMut(usize) -> Result<T, Error>,
+{
+    struct Partial<T, const N: usize> {
+        arr: [MaybeUninit<T>; N],
+        len: usize,
+    }
+
+    impl<T, const N: usize> Drop for Partial<T, N> {
+        fn drop(&mut self) {
+            for elem in &mut self.arr[..self.len] {
+                // SAFETY: The first `len` elements are initialized.
+                unsafe { elem.assume_init_drop() };
+            }
         }
-        Ok(arr)
     }
+
+    let mut partial = Partial {
+        arr: [(); N].map(|_| MaybeUninit::uninit()),
+        len: 0,
+    };
+    while partial.len < N {
+        partial.arr[partial.len] = MaybeUninit::new(f(partial.len)?);
+        partial.len += 1;
+    }
+    // SAFETY: All `N` elements are initialized, and `MaybeUninit<T>` has the same layout as `T`.
+    // The partial array is forgotten afterwards, so the elements are not dropped twice.
+    let arr = unsafe { ptr::read(partial.arr.as_ptr() as *const [T; N]) };
+    mem::forget(partial);
+    Ok(arr)
 }
 
 impl<T, Ctx> Encode<(Len, Ctx)> for Vec<T>
@@ -398,11 +756,7 @@ where
     where
         R: io::Read,
     {
-        let mut acc = Self::with_capacity(len);
-        for _ in 0..len {
-            acc.push(T::decode(inner_ctx.clone(), reader)?);
-        }
-        Ok(acc)
+        seq::decode_seq(inner_ctx, len, reader)
     }
 }
 
@@ -422,6 +776,263 @@ where
     }
 }
 
+impl<T, Ctx> Encode<(ByteLen, Ctx)> for Vec<T>
+where
+    T: Encode<Ctx>,
+    Ctx: Clone,
+{
+    /// Encodes each element of the vector in order.
+    ///
+    /// If the byte length is also to be encoded, it has to be done separately.
+    ///
+    /// The byte length context is checked against the encoded size of the elements; if they are
+    /// not equal, then this function will return an error.
+    fn encode<W>(&self, ctx: (ByteLen, Ctx), writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.as_slice().encode(ctx, writer)
+    }
+}
+
+impl<T> Encode<ByteLen> for Vec<T>
+where
+    T: Encode,
+{
+    /// Encodes each element of the vector in order.
+    ///
+    /// If the byte length is also to be encoded, it has to be done separately.
+    ///
+    /// The byte length context is checked against the encoded size of the elements; if they are
+    /// not equal, then this function will return an error.
+    fn encode<W>(&self, ctx: ByteLen, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.as_slice().encode(ctx, writer)
+    }
+}
+
+impl<T, Ctx> Encode<(ToEnd, Ctx)> for Vec<T>
+where
+    T: Encode<Ctx>,
+    Ctx: Clone,
+{
+    /// Encodes each element of the vector in order.
+    ///
+    /// Nothing else is written; the end of the sequence is marked by the end of the stream.
+    fn encode<W>(&self, ctx: (ToEnd, Ctx), writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.as_slice().encode(ctx, writer)
+    }
+}
+
+impl<T> Encode<ToEnd> for Vec<T>
+where
+    T: Encode,
+{
+    /// Encodes each element of the vector in order.
+    ///
+    /// Nothing else is written; the end of the sequence is marked by the end of the stream.
+    fn encode<W>(&self, ctx: ToEnd, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.as_slice().encode(ctx, writer)
+    }
+}
+
+impl<T, Ctx> Encode<(Until<T>, Ctx)> for Vec<T>
+where
+    T: Encode<Ctx> + PartialEq,
+    Ctx: Clone,
+{
+    /// Encodes each element of the vector in order, followed by the terminator.
+    ///
+    /// If one of the elements is equal to the terminator, this function will return an error,
+    /// since the sequence would end early when decoding it.
+    fn encode<W>(&self, ctx: (Until<T>, Ctx), writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.as_slice().encode(ctx, writer)
+    }
+}
+
+impl<T> Encode<Until<T>> for Vec<T>
+where
+    T: Encode + PartialEq,
+{
+    /// Encodes each element of the vector in order, followed by the terminator.
+    ///
+    /// If one of the elements is equal to the terminator, this function will return an error,
+    /// since the sequence would end early when decoding it.
+    fn encode<W>(&self, ctx: Until<T>, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        self.as_slice().encode(ctx, writer)
+    }
+}
+
+impl<T, Ctx> Decode<(ByteLen, Ctx)> for Vec<T>
+where
+    T: Decode<Ctx>,
+    Ctx: Clone,
+{
+    /// Decodes multiple values of type `T` from the number of bytes given by the `ByteLen`
+    /// context, collecting them in a `Vec`.
+    ///
+    /// Values are decoded until all of the bytes are consumed. If the last value extends past the
+    /// end, this function will return an error.
+    fn decode<R>((ByteLen(len), inner_ctx): (ByteLen, Ctx), reader: &mut R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        util::decode_byte_len(len, false, reader, |sub_reader| {
+            Self::decode((ToEnd, inner_ctx), sub_reader)
+        })
+    }
+}
+
+impl<T> Decode<ByteLen> for Vec<T>
+where
+    T: Decode,
+{
+    /// Decodes multiple values of type `T` from the number of bytes given by the `ByteLen`
+    /// context, collecting them in a `Vec`.
+    ///
+    /// Values are decoded until all of the bytes are consumed. If the last value extends past the
+    /// end, this function will return an error.
+    fn decode<R>(byte_len: ByteLen, reader: &mut R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        Self::decode((byte_len, ()), reader)
+    }
+}
+
+impl<T, Ctx> Decode<(ToEnd, Ctx)> for Vec<T>
+where
+    T: Decode<Ctx>,
+    Ctx: Clone,
+{
+    /// Decodes values of type `T` until the end of the stream, collecting them in a `Vec`.
+    ///
+    /// Reaching the end of the stream between two elements ends the sequence, but reaching it in
+    /// the middle of an element is an error.
+    fn decode<R>((ToEnd, inner_ctx): (ToEnd, Ctx), reader: &mut R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        let mut acc = Self::new();
+        while let Some(elem) = decode_next(inner_ctx.clone(), reader)? {
+            limits::push(&mut acc, elem)?;
+        }
+        Ok(acc)
+    }
+}
+
+impl<T> Decode<ToEnd> for Vec<T>
+where
+    T: Decode,
+{
+    /// Decodes values of type `T` until the end of the stream, collecting them in a `Vec`.
+    ///
+    /// Reaching the end of the stream between two elements ends the sequence, but reaching it in
+    /// the middle of an element is an error.
+    fn decode<R>(to_end: ToEnd, reader: &mut R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        Self::decode((to_end, ()), reader)
+    }
+}
+
+impl<T, Ctx> Decode<(Until<T>, Ctx)> for Vec<T>
+where
+    T: Decode<Ctx> + PartialEq,
+    Ctx: Clone,
+{
+    /// Decodes values of type `T` until one is equal to the terminator, collecting them in a `Vec`.
+    ///
+    /// The terminator is consumed, but is not included in the result. Reaching the end of the
+    /// stream before the terminator is an error.
+    fn decode<R>(
+        (Until(terminator), inner_ctx): (Until<T>, Ctx),
+        reader: &mut R,
+    ) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        let mut acc = Self::new();
+        loop {
+            match decode_next(inner_ctx.clone(), reader)? {
+                Some(elem) if elem == terminator => return Ok(acc),
+                Some(elem) => limits::push(&mut acc, elem)?,
+                None => {
+                    return Err(Error::with_kind(
+                        ErrorKind::UnexpectedEof,
+                        "reached end of stream before terminator",
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl<T> Decode<Until<T>> for Vec<T>
+where
+    T: Decode + PartialEq,
+{
+    /// Decodes values of type `T` until one is equal to the terminator, collecting them in a `Vec`.
+    ///
+    /// The terminator is consumed, but is not included in the result. Reaching the end of the
+    /// stream before the terminator is an error.
+    fn decode<R>(until: Until<T>, reader: &mut R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        Self::decode((until, ()), reader)
+    }
+}
+
+/// Decodes the next element of a sequence, or returns `None` if the reader is already at the end
+/// of the stream.
+pub(crate) fn decode_next<T, Ctx, R>(ctx: Ctx, reader: &mut R) -> Result<Option<T>, Error>
+where
+    T: Decode<Ctx>,
+    R: io::Read,
+{
+    // Peek a single byte to find out whether we are at the end, then chain it back in front of
+    // the reader for the element decoder. It is given back to the trace too, if one is recorded.
+    let mut byte = [0u8];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => break,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error.into()),
+        }
+    }
+    let mut peeked = trace::Unread::new(&byte);
+    let elem = T::decode(ctx, &mut io::Read::chain(&mut peeked, &mut *reader)).map_err(|e| {
+        if e.kind() == ErrorKind::UnexpectedEof {
+            Error::with_context("truncated element at end of stream", e)
+        } else {
+            Error::with_context("error decoding element", e)
+        }
+    })?;
+    if !peeked.is_empty() {
+        return Err(Error::new(
+            "element did not consume any bytes; it cannot be decoded until the end of stream",
+        ));
+    }
+    Ok(Some(elem))
+}
+
 impl<T, Ctx> Encode<Ctx> for Option<T>
 where
     T: Encode<Ctx>,
@@ -468,7 +1079,7 @@ where
     where
         W: io::Write,
     {
-        T::encode(&*self, inner_ctx, writer)
+        T::encode(&**self, inner_ctx, writer)
     }
 }
 
@@ -495,7 +1106,7 @@ where
     where
         W: io::Write,
     {
-        T::encode(&*self, inner_ctx, writer)
+        T::encode(&**self, inner_ctx, writer)
     }
 }
 
@@ -508,7 +1119,9 @@ where
     where
         R: io::Read,
     {
-        T::decode(inner_ctx, reader).map(Self::new)
+        let value = T::decode(inner_ctx, reader)?;
+        limits::alloc(mem::size_of::<T>())?;
+        Ok(Self::new(value))
     }
 }
 
@@ -532,6 +1145,124 @@ impl Decode for () {
     }
 }
 
+// Tuples accept either a tuple of contexts, one per element, or a single context that is shared by
+// every element. A blanket impl over `Ctx: Clone` would overlap with the per-element form, so the
+// shared form is implemented for the common context types, and for any other cloneable context
+// wrapped in `Shared`.
+macro_rules! impl_tuple {
+    ($($t:ident $c:ident $idx:tt),*) => {
+        impl<$($t, $c),*> Encode<($($c,)*)> for ($($t,)*)
+        where
+            $($t: Encode<$c>,)*
+        {
+            /// Encodes each element in order, passing it the corresponding element of the context
+            /// tuple.
+            fn encode<W>(&self, ctx: ($($c,)*), writer: &mut W) -> Result<(), Error>
+            where
+                W: io::Write,
+            {
+                $(self.$idx.encode(ctx.$idx, writer)?;)*
+                Ok(())
+            }
+        }
+
+        impl<$($t, $c),*> Decode<($($c,)*)> for ($($t,)*)
+        where
+            $($t: Decode<$c>,)*
+        {
+            /// Decodes each element in order, passing it the corresponding element of the context
+            /// tuple.
+            fn decode<R>(ctx: ($($c,)*), reader: &mut R) -> Result<Self, Error>
+            where
+                R: io::Read,
+            {
+                Ok(($($t::decode(ctx.$idx, reader)?,)*))
+            }
+        }
+
+        impl<Ctx, $($t),*> Encode<Shared<Ctx>> for ($($t,)*)
+        where
+            Ctx: Clone,
+            $($t: Encode<Ctx>,)*
+        {
+            /// Encodes each element in order, passing a clone of the same context to all of them.
+            fn encode<W>(&self, Shared(ctx): Shared<Ctx>, writer: &mut W) -> Result<(), Error>
+            where
+                W: io::Write,
+            {
+                $(self.$idx.encode(ctx.clone(), writer)?;)*
+                Ok(())
+            }
+        }
+
+        impl<Ctx, $($t),*> Decode<Shared<Ctx>> for ($($t,)*)
+        where
+            Ctx: Clone,
+            $($t: Decode<Ctx>,)*
+        {
+            /// Decodes each element in order, passing a clone of the same context to all of them.
+            fn decode<R>(Shared(ctx): Shared<Ctx>, reader: &mut R) -> Result<Self, Error>
+            where
+                R: io::Read,
+            {
+                Ok(($($t::decode(ctx.clone(), reader)?,)*))
+            }
+        }
+
+        impl_tuple!(@shared [$($t $idx),*] ());
+        impl_tuple!(@shared [$($t $idx),*] Endian);
+    };
+
+    (@shared [$($t:ident $idx:tt),*] $ctx:ty) => {
+        impl<$($t),*> Encode<$ctx> for ($($t,)*)
+        where
+            $($t: Encode<$ctx>,)*
+        {
+            /// Encodes each element in order, passing the same context to all of them.
+            fn encode<W>(&self, ctx: $ctx, writer: &mut W) -> Result<(), Error>
+            where
+                W: io::Write,
+            {
+                self.encode(Shared(ctx), writer)
+            }
+        }
+
+        impl<$($t),*> Decode<$ctx> for ($($t,)*)
+        where
+            $($t: Decode<$ctx>,)*
+        {
+            /// Decodes each element in order, passing the same context to all of them.
+            fn decode<R>(ctx: $ctx, reader: &mut R) -> Result<Self, Error>
+            where
+                R: io::Read,
+            {
+                Self::decode(Shared(ctx), reader)
+            }
+        }
+    };
+}
+
+impl_tuple!(T0 C0 0);
+impl_tuple!(T0 C0 0, T1 C1 1);
+impl_tuple!(T0 C0 0, T1 C1 1, T2 C2 2);
+impl_tuple!(T0 C0 0, T1 C1 1, T2 C2 2, T3 C3 3);
+impl_tuple!(T0 C0 0, T1 C1 1, T2 C2 2, T3 C3 3, T4 C4 4);
+impl_tuple!(T0 C0 0, T1 C1 1, T2 C2 2, T3 C3 3, T4 C4 4, T5 C5 5);
+impl_tuple!(T0 C0 0, T1 C1 1, T2 C2 2, T3 C3 3, T4 C4 4, T5 C5 5, T6 C6 6);
+impl_tuple!(T0 C0 0, T1 C1 1, T2 C2 2, T3 C3 3, T4 C4 4, T5 C5 5, T6 C6 6, T7 C7 7);
+impl_tuple!(T0 C0 0, T1 C1 1, T2 C2 2, T3 C3 3, T4 C4 4, T5 C5 5, T6 C6 6, T7 C7 7, T8 C8 8);
+impl_tuple!(
+    T0 C0 0, T1 C1 1, T2 C2 2, T3 C3 3, T4 C4 4, T5 C5 5, T6 C6 6, T7 C7 7, T8 C8 8, T9 C9 9
+);
+impl_tuple!(
+    T0 C0 0, T1 C1 1, T2 C2 2, T3 C3 3, T4 C4 4, T5 C5 5, T6 C6 6, T7 C7 7, T8 C8 8, T9 C9 9,
+    T10 C10 10
+);
+impl_tuple!(
+    T0 C0 0, T1 C1 1, T2 C2 2, T3 C3 3, T4 C4 4, T5 C5 5, T6 C6 6, T7 C7 7, T8 C8 8, T9 C9 9,
+    T10 C10 10, T11 C11 11
+);
+
 macro_rules! impl_primitive {
     ($($t:ty)*) => {$(
         impl Encode<Endian> for $t {
@@ -539,10 +1270,8 @@ macro_rules! impl_primitive {
             where
                 W: io::Write,
             {
-                let bytes = match endian {
-                    Endian::Big => self.to_be_bytes(),
-                    Endian::Little => self.to_le_bytes(),
-                };
+                let mut bytes = self.to_be_bytes();
+                from_big_endian(&mut bytes, endian);
                 writer.write_all(&bytes)?;
                 Ok(())
             }
@@ -555,15 +1284,46 @@ macro_rules! impl_primitive {
             {
                 let mut bytes = [0u8; mem::size_of::<$t>()];
                 reader.read_exact(&mut bytes)?;
-                match endian {
-                    Endian::Big => Ok(Self::from_be_bytes(bytes)),
-                    Endian::Little => Ok(Self::from_le_bytes(bytes)),
-                }
+                to_big_endian(&mut bytes, endian);
+                Ok(Self::from_be_bytes(bytes))
             }
         }
     )*}
 }
 
+/// Rearranges the big-endian representation of a primitive into the given byte order.
+pub(crate) fn from_big_endian(bytes: &mut [u8], endian: Endian) {
+    match endian {
+        Endian::Big => {}
+        Endian::Little => bytes.reverse(),
+        Endian::BigWordSwapped => {
+            bytes.reverse();
+            swap_byte_pairs(bytes);
+        }
+        Endian::LittleWordSwapped => swap_byte_pairs(bytes),
+    }
+}
+
+/// Rearranges a primitive in the given byte order into its big-endian representation.
+pub(crate) fn to_big_endian(bytes: &mut [u8], endian: Endian) {
+    match endian {
+        Endian::Big => {}
+        Endian::Little => bytes.reverse(),
+        Endian::BigWordSwapped => {
+            swap_byte_pairs(bytes);
+            bytes.reverse();
+        }
+        Endian::LittleWordSwapped => swap_byte_pairs(bytes),
+    }
+}
+
+/// Swaps the two bytes of each 16-bit word.
+fn swap_byte_pairs(bytes: &mut [u8]) {
+    for pair in bytes.chunks_exact_mut(2) {
+        pair.swap(0, 1);
+    }
+}
+
 impl_primitive! {
     u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64
 }
@@ -606,3 +1366,137 @@ impl Decode for i8 {
         Self::decode(Endian::Big, reader)
     }
 }
+
+impl Encode<BoolRepr> for bool {
+    fn encode<W>(&self, repr: BoolRepr, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        match repr {
+            BoolRepr::ZeroOne | BoolRepr::NonZero => (*self as u8).encode((), writer),
+            BoolRepr::ZeroFF => (if *self { 0xffu8 } else { 0x00u8 }).encode((), writer),
+            BoolRepr::Int { size, endian, .. } => {
+                let bytes = int_bytes(*self as u64, size, endian)?;
+                writer.write_all(&bytes[..size])?;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Decode<BoolRepr> for bool {
+    fn decode<R>(repr: BoolRepr, reader: &mut R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        let (value, strict) = match repr {
+            BoolRepr::ZeroOne => (u8::decode((), reader)?.into(), true),
+            BoolRepr::NonZero => (u8::decode((), reader)?.into(), false),
+            BoolRepr::ZeroFF => {
+                return match u8::decode((), reader)? {
+                    0x00 => Ok(false),
+                    0xff => Ok(true),
+                    byte => Err(Error::new(format!(
+                        "invalid byte value for boolean: expected 0x00 or 0xff, got {:#04x}",
+                        byte
+                    ))),
+                };
+            }
+            BoolRepr::Int {
+                size,
+                endian,
+                strict,
+            } => {
+                let mut bytes = int_bytes(0, size, endian)?;
+                reader.read_exact(&mut bytes[..size])?;
+                to_big_endian(&mut bytes[..size], endian);
+                let value = u64::from_be_bytes(bytes) >> (64 - 8 * size);
+                (value, strict)
+            }
+        };
+        match value {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ if !strict => Ok(true),
+            _ => Err(Error::new(format!(
+                "invalid value for boolean: expected 0 or 1, got {:?}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Returns a buffer whose first `size` bytes are `value` in the given byte order.
+// `usize::is_multiple_of` is too recent for the versions of Rust this crate supports.
+#[allow(clippy::manual_is_multiple_of)]
+fn int_bytes(value: u64, size: usize, endian: Endian) -> Result<[u8; 8], Error> {
+    if !(1..=8).contains(&size) {
+        return Err(Error::new(format!(
+            "invalid integer size for boolean: expected 1 to 8 bytes, got {}",
+            size
+        )));
+    }
+    let word_swapped = matches!(endian, Endian::BigWordSwapped | Endian::LittleWordSwapped);
+    if word_swapped && size % 2 != 0 {
+        return Err(Error::new(format!(
+            "invalid integer size for word-swapped boolean: expected an even number of bytes, got {}",
+            size
+        )));
+    }
+    let mut bytes = (value << (64 - 8 * size)).to_be_bytes();
+    from_big_endian(&mut bytes[..size], endian);
+    Ok(bytes)
+}
+
+impl Encode<CharRepr> for char {
+    fn encode<W>(&self, repr: CharRepr, writer: &mut W) -> Result<(), Error>
+    where
+        W: io::Write,
+    {
+        match repr {
+            CharRepr::Utf8 => {
+                let mut buf = [0u8; 4];
+                writer.write_all(self.encode_utf8(&mut buf).as_bytes())?;
+                Ok(())
+            }
+            CharRepr::Utf32(endian) => u32::from(*self).encode(endian, writer),
+            CharRepr::Latin1 => {
+                let byte = u8::try_from(u32::from(*self)).map_err(|e| {
+                    Error::with_context(format!("character {:?} is not in Latin-1", self), e)
+                })?;
+                byte.encode((), writer)
+            }
+        }
+    }
+}
+
+impl Decode<CharRepr> for char {
+    fn decode<R>(repr: CharRepr, reader: &mut R) -> Result<Self, Error>
+    where
+        R: io::Read,
+    {
+        match repr {
+            CharRepr::Utf8 => {
+                let mut buf = [0u8; 4];
+                reader.read_exact(&mut buf[..1])?;
+                let len = match buf[0] {
+                    0x00..=0x7f => 1,
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    byte => {
+                        return Err(Error::new(format!(
+                            "invalid leading byte for UTF-8 character: {:#04x}",
+                            byte
+                        )))
+                    }
+                };
+                reader.read_exact(&mut buf[1..len])?;
+                let s = std::str::from_utf8(&buf[..len])?;
+                Ok(s.chars().next().unwrap())
+            }
+            CharRepr::Utf32(endian) => Ok(char::try_from(u32::decode(endian, reader)?)?),
+            CharRepr::Latin1 => Ok(u8::decode((), reader)?.into()),
+        }
+    }
+}
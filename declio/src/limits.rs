@@ -0,0 +1,213 @@
+use crate::{Error, ErrorKind};
+use std::cell::Cell;
+use std::mem;
+
+/// The maximum number of bytes that containers will preallocate before any elements have been
+/// decoded. Containers larger than this grow incrementally as elements are decoded.
+pub(crate) const PREALLOC_BYTES: usize = 4096;
+
+/// Limits on the resources used while decoding, to protect against malicious input.
+///
+/// Length values like [`Len`](crate::ctx::Len) usually come from the input itself, so without
+/// limits, a few bytes can make a decoder attempt to allocate gigabytes of memory. Similarly,
+/// recursive types can be nested deeply enough to overflow the stack. Limits are applied to
+/// everything decoded while they are in [`scope`](Limits::scope), and exceeding them returns an
+/// error of kind [`LimitExceeded`](ErrorKind::LimitExceeded) or
+/// [`DepthExceeded`](ErrorKind::DepthExceeded).
+///
+/// By default, decoding is unlimited.
+///
+/// # Example
+///
+/// ```
+/// use declio::Limits;
+/// use declio::ctx::Len;
+///
+/// let limits = Limits {
+///     max_len: 1024,
+///     ..Limits::default()
+/// };
+///
+/// let result: Result<Vec<u8>, _> = declio::from_bytes_with_limits(&[0; 4], Len(1 << 30), limits);
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// The maximum number of bytes allocated by containers in total, over the entire scope.
+    pub max_alloc: usize,
+    /// The maximum number of elements in a single container.
+    pub max_len: usize,
+    /// The maximum nesting depth of decoded values, as counted by [`DepthGuard`].
+    pub max_depth: usize,
+}
+
+impl Limits {
+    /// Limits that never restrict decoding.
+    pub const fn unlimited() -> Self {
+        Self {
+            max_alloc: usize::MAX,
+            max_len: usize::MAX,
+            max_depth: usize::MAX,
+        }
+    }
+
+    /// Calls `f` with these limits applied to all decoding done on the current thread.
+    ///
+    /// Limits replace those of an enclosing scope; they are not combined.
+    pub fn scope<F, T>(self, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        struct Restore(State);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                STATE.with(|state| state.set(self.0));
+            }
+        }
+
+        let _restore = Restore(STATE.with(|state| state.replace(State::new(self))));
+        f()
+    }
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+/// Tracks the nesting depth of decoded values.
+///
+/// Entering a guard increases the depth until the guard is dropped, and fails if that exceeds
+/// [`Limits::max_depth`]. Derived `Decode` implementations enter a guard before decoding their
+/// fields, so recursive types built from them are limited automatically. Manual implementations
+/// of recursive types should do the same:
+///
+/// ```
+/// use declio::{Decode, DepthGuard, Error, ErrorKind, Limits};
+///
+/// /// A list of bytes, terminated by a zero.
+/// struct List(u8, Option<Box<List>>);
+///
+/// impl Decode for List {
+///     fn decode<R>(_ctx: (), reader: &mut R) -> Result<Self, Error>
+///     where
+///         R: std::io::Read,
+///     {
+///         let _guard = DepthGuard::enter()?;
+///         let head = u8::decode((), reader)?;
+///         let tail = match head {
+///             0 => None,
+///             _ => Some(Box::decode((), reader)?),
+///         };
+///         Ok(List(head, tail))
+///     }
+/// }
+///
+/// let limits = Limits {
+///     max_depth: 8,
+///     ..Limits::default()
+/// };
+/// assert!(declio::from_bytes_with_limits::<List, _>(&[1, 2, 3, 0], (), limits).is_ok());
+///
+/// let error = declio::from_bytes_with_limits::<List, _>(&[1; 16], (), limits).err().unwrap();
+/// assert_eq!(error.kind(), ErrorKind::DepthExceeded);
+/// ```
+pub struct DepthGuard {
+    _private: (),
+}
+
+impl DepthGuard {
+    /// Increases the nesting depth, returning an error if it exceeds the current limit.
+    pub fn enter() -> Result<Self, Error> {
+        STATE.with(|cell| {
+            let mut state = cell.get();
+            if state.depth >= state.max_depth {
+                return Err(Error::with_kind(
+                    ErrorKind::DepthExceeded,
+                    format!("decoding exceeded the depth limit of {}", state.max_depth),
+                ));
+            }
+            state.depth += 1;
+            cell.set(state);
+            Ok(Self { _private: () })
+        })
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        STATE.with(|cell| {
+            let mut state = cell.get();
+            state.depth -= 1;
+            cell.set(state);
+        });
+    }
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    max_len: usize,
+    max_depth: usize,
+    remaining_alloc: usize,
+    depth: usize,
+}
+
+impl State {
+    const fn new(limits: Limits) -> Self {
+        Self {
+            max_len: limits.max_len,
+            max_depth: limits.max_depth,
+            remaining_alloc: limits.max_alloc,
+            depth: 0,
+        }
+    }
+}
+
+thread_local! {
+    static STATE: Cell<State> = const { Cell::new(State::new(Limits::unlimited())) };
+}
+
+/// Checks the length of a container against the current limits.
+pub(crate) fn check_len(len: usize) -> Result<(), Error> {
+    let max_len = STATE.with(|state| state.get().max_len);
+    if len > max_len {
+        return Err(Error::with_kind(
+            ErrorKind::LimitExceeded,
+            format!("container length {} exceeds the limit of {}", len, max_len),
+        ));
+    }
+    Ok(())
+}
+
+/// Charges an allocation of `size` bytes against the current limits.
+pub(crate) fn alloc(size: usize) -> Result<(), Error> {
+    STATE.with(|cell| {
+        let mut state = cell.get();
+        state.remaining_alloc = state.remaining_alloc.checked_sub(size).ok_or_else(|| {
+            Error::with_kind(
+                ErrorKind::LimitExceeded,
+                "decoding exceeded the allocation limit",
+            )
+        })?;
+        cell.set(state);
+        Ok(())
+    })
+}
+
+/// Creates a vector for the given number of elements, without trusting that number for more than
+/// a small preallocation.
+pub(crate) fn vec_for_len<T>(len: usize) -> Result<Vec<T>, Error> {
+    check_len(len)?;
+    let max_prealloc = PREALLOC_BYTES / mem::size_of::<T>().max(1);
+    Ok(Vec::with_capacity(len.min(max_prealloc)))
+}
+
+/// Pushes a decoded element onto a vector, checking it against the current limits.
+pub(crate) fn push<T>(vec: &mut Vec<T>, elem: T) -> Result<(), Error> {
+    check_len(vec.len() + 1)?;
+    alloc(mem::size_of::<T>())?;
+    vec.push(elem);
+    Ok(())
+}
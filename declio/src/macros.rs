@@ -55,3 +55,106 @@ macro_rules! magic_bytes {
         }
     )*}
 }
+
+/// Defines a type that encodes and decodes a byte order mark, a constant byte string that
+/// identifies the [`Endian`](crate::ctx::Endian) of the data that follows it.
+///
+/// The type is a wrapper around an `Endian`, which converts to and from it with `From`. When
+/// decoding, the bytes read are compared against both strings, and an error is returned if neither
+/// of them match. Both strings must have the same length. Encoding a word-swapped byte order is an
+/// error, since there is no marker for it.
+///
+/// The derive macros can use the decoded endianness for the rest of the fields with the
+/// `endian_from` attribute; see the [`derive`](crate::derive) module for more information.
+///
+/// # Example
+///
+/// ```
+/// use declio::ctx::Endian;
+/// use declio::util::endian_marker;
+///
+/// endian_marker! {
+///     /// The byte order of a TIFF file.
+///     pub TiffByteOrder { big: b"MM", little: b"II" }
+///
+///     /// The `EI_DATA` field of an ELF header.
+///     ElfData { big: b"\x02", little: b"\x01" }
+/// }
+///
+/// let bytes: Vec<u8> = declio::to_bytes(&TiffByteOrder(Endian::Little)).unwrap();
+/// assert_eq!(bytes, b"II");
+///
+/// let order: TiffByteOrder = declio::from_bytes(b"MM").unwrap();
+/// assert_eq!(Endian::from(order), Endian::Big);
+///
+/// assert!(declio::from_bytes::<ElfData>(b"\x03").is_err());
+/// ```
+#[macro_export]
+macro_rules! endian_marker {
+    ($($(#[$attr:meta])* $vis:vis $name:ident { big: $big:expr, little: $little:expr $(,)? })*) => {$(
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        $vis struct $name(pub $crate::ctx::Endian);
+
+        const _: () = assert!(
+            ($big).len() == ($little).len(),
+            "endian markers must have the same length",
+        );
+
+        impl $crate::Encode<()> for $name {
+            fn encode<W>(&self, _ctx: (), writer: &mut W) -> Result<(), $crate::Error>
+            where
+                W: std::io::Write,
+            {
+                let bytes = match self.0 {
+                    $crate::ctx::Endian::Big => $big,
+                    $crate::ctx::Endian::Little => $little,
+                    endian => {
+                        return Err($crate::Error::new(format!(
+                            "no endian marker for {:?} byte order",
+                            endian,
+                        )));
+                    }
+                };
+                $crate::Encode::encode(bytes, (), writer)
+            }
+        }
+
+        impl $crate::Decode<()> for $name {
+            fn decode<R>(_ctx: (), reader: &mut R) -> Result<Self, $crate::Error>
+            where
+                R: std::io::Read,
+            {
+                let bytes: [u8; ($big).len()] = $crate::Decode::decode((), reader)?;
+                if &bytes == $big {
+                    Ok(Self($crate::ctx::Endian::Big))
+                } else if &bytes == $little {
+                    Ok(Self($crate::ctx::Endian::Little))
+                } else {
+                    Err($crate::Error::new(format!(
+                        "invalid endian marker: expected {:x?} or {:x?}, got {:x?}",
+                        $big, $little, bytes,
+                    )))
+                }
+            }
+        }
+
+        impl From<$crate::ctx::Endian> for $name {
+            fn from(endian: $crate::ctx::Endian) -> Self {
+                Self(endian)
+            }
+        }
+
+        impl From<$name> for $crate::ctx::Endian {
+            fn from(marker: $name) -> Self {
+                marker.0
+            }
+        }
+
+        impl From<&$name> for $crate::ctx::Endian {
+            fn from(marker: &$name) -> Self {
+                marker.0
+            }
+        }
+    )*}
+}
@@ -0,0 +1,138 @@
+use crate::{Decode, Error, ErrorKind};
+use std::io;
+
+/// The result of decoding from a buffer that may not contain the whole value yet.
+///
+/// This is returned by [`decode_partial`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Partial<T> {
+    /// The value was decoded from the first `len` bytes of the buffer.
+    Complete {
+        /// The decoded value.
+        value: T,
+        /// The number of bytes used by the value.
+        len: usize,
+    },
+    /// The buffer ended before the value did.
+    Incomplete {
+        /// The minimum number of additional bytes needed to continue decoding, if it is known.
+        ///
+        /// More bytes than this may be needed to decode the whole value, since later fields are not
+        /// known until the earlier ones are decoded.
+        needed: Option<usize>,
+    },
+}
+
+impl<T> Partial<T> {
+    /// Returns `true` if the value was decoded.
+    pub fn is_complete(&self) -> bool {
+        matches!(self, Partial::Complete { .. })
+    }
+
+    /// Returns the decoded value, if it is complete.
+    pub fn complete(self) -> Option<T> {
+        match self {
+            Partial::Complete { value, .. } => Some(value),
+            Partial::Incomplete { .. } => None,
+        }
+    }
+}
+
+/// Decodes a value from the start of a buffer that may not contain all of it yet.
+///
+/// See [`decode_partial_with_context`] for details.
+pub fn decode_partial<T>(bytes: &[u8]) -> Result<Partial<T>, Error>
+where
+    T: Decode,
+{
+    decode_partial_with_context(bytes, ())
+}
+
+/// Decodes a value from the start of a buffer that may not contain all of it yet, with context.
+///
+/// This is meant for input that arrives in chunks, like from a non-blocking socket. If the buffer
+/// ends before the value does, this returns [`Partial::Incomplete`] instead of an error, so that
+/// the caller can retry once more bytes have been appended. Decoding starts over from the
+/// beginning of the buffer on each call. Errors are only returned for input that can't be decoded
+/// regardless of what follows it.
+///
+/// Unlike [`from_bytes`](crate::from_bytes), the value does not need to use the whole buffer; the
+/// number of bytes it used is returned with it, and any bytes after them are left for the next
+/// value.
+///
+/// Values that are decoded with [`ToEnd`](crate::ctx::ToEnd) end wherever the buffer currently
+/// ends, so they are always complete.
+///
+/// # Example
+///
+/// ```
+/// use declio::Partial;
+/// use declio::ctx::Endian;
+///
+/// let mut buf = vec![0x12];
+/// let result = declio::decode_partial_with_context::<u32, _>(&buf, Endian::Big).unwrap();
+/// assert_eq!(result, Partial::Incomplete { needed: Some(3) });
+///
+/// buf.extend_from_slice(&[0x34, 0x56, 0x78, 0x9a]);
+/// let result = declio::decode_partial_with_context::<u32, _>(&buf, Endian::Big).unwrap();
+/// assert_eq!(result, Partial::Complete { value: 0x12345678, len: 4 });
+/// ```
+pub fn decode_partial_with_context<T, Ctx>(bytes: &[u8], ctx: Ctx) -> Result<Partial<T>, Error>
+where
+    T: Decode<Ctx>,
+{
+    let mut reader = PartialReader {
+        bytes,
+        pos: 0,
+        end: None,
+    };
+    match T::decode(ctx, &mut reader) {
+        Ok(value) => Ok(Partial::Complete {
+            value,
+            len: reader.pos,
+        }),
+        Err(error) => match reader.end {
+            // Readers that limit the input, like the one for `byte_len`, can also fail with an
+            // unexpected end before the buffer ends. Those errors are real.
+            Some(needed) if error.kind() == ErrorKind::UnexpectedEof => {
+                Ok(Partial::Incomplete { needed })
+            }
+            _ => Err(error),
+        },
+    }
+}
+
+/// A reader over a buffer, which records whether a read was attempted past its end.
+struct PartialReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// The number of bytes missing from the last read past the end, if it is known.
+    end: Option<Option<usize>>,
+}
+
+impl io::Read for PartialReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = &self.bytes[self.pos..];
+        if remaining.is_empty() && !buf.is_empty() {
+            // The reader may have wanted fewer bytes than the size of its buffer.
+            self.end = Some(None);
+            return Ok(0);
+        }
+        let len = buf.len().min(remaining.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.pos += len;
+        Ok(len)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        let remaining = &self.bytes[self.pos..];
+        if buf.len() > remaining.len() {
+            self.end = Some(Some(buf.len() - remaining.len()));
+            self.pos = self.bytes.len();
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.copy_from_slice(&remaining[..buf.len()]);
+        self.pos += buf.len();
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use crate::ctx::Endian;
+use crate::{from_big_endian, to_big_endian};
+use std::convert::TryInto;
+use std::mem;
+
+/// Fixed-size values that can be converted to and from bytes without any context other than the
+/// byte order.
+///
+/// This is used by derive macros to implement the `pod` attribute. It is implemented for the
+/// integer and floating-point primitives, and arrays of them.
+#[doc(hidden)]
+pub trait Pod: Sized {
+    /// The number of bytes occupied by the value.
+    const SIZE: usize;
+
+    /// Converts the value from exactly `SIZE` bytes.
+    fn read_pod(bytes: &[u8], endian: Endian) -> Self;
+
+    /// Converts the value into exactly `SIZE` bytes.
+    fn write_pod(&self, bytes: &mut [u8], endian: Endian);
+}
+
+macro_rules! impl_pod {
+    ($($t:ty)*) => {$(
+        impl Pod for $t {
+            const SIZE: usize = mem::size_of::<$t>();
+
+            fn read_pod(bytes: &[u8], endian: Endian) -> Self {
+                let mut bytes: [u8; mem::size_of::<$t>()] =
+                    bytes.try_into().expect("wrong number of bytes for pod value");
+                to_big_endian(&mut bytes, endian);
+                Self::from_be_bytes(bytes)
+            }
+
+            fn write_pod(&self, bytes: &mut [u8], endian: Endian) {
+                bytes.copy_from_slice(&self.to_be_bytes());
+                from_big_endian(bytes, endian);
+            }
+        }
+    )*}
+}
+
+impl_pod! {
+    u8 u16 u32 u64 u128 i8 i16 i32 i64 i128 f32 f64
+}
+
+impl<T, const N: usize> Pod for [T; N]
+where
+    T: Pod,
+{
+    const SIZE: usize = T::SIZE * N;
+
+    fn read_pod(bytes: &[u8], endian: Endian) -> Self {
+        std::array::from_fn(|index| T::read_pod(&bytes[index * T::SIZE..][..T::SIZE], endian))
+    }
+
+    fn write_pod(&self, bytes: &mut [u8], endian: Endian) {
+        if T::SIZE == 0 {
+            return;
+        }
+        for (elem, chunk) in self.iter().zip(bytes.chunks_exact_mut(T::SIZE)) {
+            elem.write_pod(chunk, endian);
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Encoding and decoding of sequences.
+//!
+//! The implementations for slices, vectors and arrays share these functions. With the `bulk`
+//! feature, sequences of primitives are read or written all at once; see the `bulk` module.
+
+#[cfg(feature = "bulk")]
+use crate::bulk::{cast_slice, cast_vec, specialize, Primitive};
+use crate::{limits, try_array, Decode, Encode, Error};
+use std::io;
+
+/// Encodes each element of a slice in order, with the same context.
+pub(crate) fn encode_seq<T, Ctx, W>(slice: &[T], ctx: Ctx, writer: &mut W) -> Result<(), Error>
+where
+    T: Encode<Ctx>,
+    Ctx: Clone,
+    W: io::Write,
+{
+    #[cfg(feature = "bulk")]
+    specialize!(T, &ctx, |P, endian| {
+        // SAFETY: `T` is `P`.
+        P::encode_seq(unsafe { cast_slice(slice) }, endian, writer)
+    });
+    for elem in slice {
+        elem.encode(ctx.clone(), writer)?;
+    }
+    Ok(())
+}
+
+/// Decodes `len` values in order, with the same context.
+pub(crate) fn decode_seq<T, Ctx, R>(ctx: Ctx, len: usize, reader: &mut R) -> Result<Vec<T>, Error>
+where
+    T: Decode<Ctx>,
+    Ctx: Clone,
+    R: io::Read,
+{
+    #[cfg(feature = "bulk")]
+    specialize!(T, &ctx, |P, endian| {
+        // SAFETY: `T` is `P`.
+        P::decode_seq(endian, len, reader).map(|vec| unsafe { cast_vec(vec) })
+    });
+    let mut acc = limits::vec_for_len(len)?;
+    for _ in 0..len {
+        limits::push(&mut acc, T::decode(ctx.clone(), reader)?)?;
+    }
+    Ok(acc)
+}
+
+/// Decodes an array of values in order, with the same context.
+pub(crate) fn decode_array<T, Ctx, R, const N: usize>(
+    ctx: Ctx,
+    reader: &mut R,
+) -> Result<[T; N], Error>
+where
+    T: Decode<Ctx>,
+    Ctx: Clone,
+    R: io::Read,
+{
+    #[cfg(feature = "bulk")]
+    specialize!(T, &ctx, |P, endian| {
+        // SAFETY: `T` is `P`, and primitives are `Copy`, so the original array needs no drop.
+        P::decode_array::<R, N>(endian, reader).map(|arr| unsafe { std::mem::transmute_copy(&arr) })
+    });
+    try_array(|_| T::decode(ctx.clone(), reader))
+}
@@ -0,0 +1,205 @@
+//! Assertions for testing `Encode` and `Decode` implementations.
+//!
+//! This module is only available with the `testing` feature, which is meant to be enabled in
+//! `dev-dependencies`:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! declio = { version = "0.2", features = ["testing"] }
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use declio::{Decode, Encode, ErrorKind};
+//! use declio::testing::{assert_decode_error, assert_roundtrip};
+//!
+//! #[derive(Debug, PartialEq, Encode, Decode)]
+//! #[declio(endian = "big")]
+//! struct Header {
+//!     version: u8,
+//!     len: u16,
+//! }
+//!
+//! assert_roundtrip(&Header { version: 1, len: 4 }, (), &[0x01, 0x00, 0x04]);
+//! assert_decode_error::<Header, _>(&[0x01, 0x00], (), ErrorKind::UnexpectedEof);
+//! ```
+
+use crate::{Decode, Encode, Error, ErrorKind};
+use std::fmt::{self, Debug, Write};
+use std::fs;
+use std::path::Path;
+
+/// Asserts that `value` encodes to `expected` with the given context, and that `expected` decodes
+/// back to `value`.
+///
+/// Decoding must consume all of `expected`, like [`from_bytes`](crate::from_bytes).
+///
+/// # Panics
+///
+/// Panics if encoding or decoding fails, or if either result differs from what is expected.
+#[track_caller]
+pub fn assert_roundtrip<T, Ctx>(value: &T, ctx: Ctx, expected: &[u8])
+where
+    T: Encode<Ctx> + Decode<Ctx> + Debug + PartialEq,
+    Ctx: Clone,
+{
+    let bytes = match crate::to_bytes_with_context(value, ctx.clone()) {
+        Ok(bytes) => bytes,
+        Err(error) => panic!("failed to encode {:?}: {}", value, Chain(&error)),
+    };
+    if let Some(mismatch) = mismatch(expected, &bytes) {
+        panic!("encoding {:?} gave unexpected bytes\n{}", value, mismatch);
+    }
+
+    match crate::from_bytes_with_context::<T, Ctx>(expected, ctx) {
+        Ok(decoded) => assert_eq!(
+            &decoded, value,
+            "decoding gave a different value than was encoded"
+        ),
+        Err(error) => panic!("failed to decode {:?}: {}", value, Chain(&error)),
+    }
+}
+
+/// Asserts that decoding `bytes` with the given context fails with an error of the given kind,
+/// and returns the error for further checks.
+///
+/// Like [`from_bytes`](crate::from_bytes), it is an error if decoding does not consume all of
+/// `bytes`.
+///
+/// # Panics
+///
+/// Panics if decoding succeeds, or fails with a different kind of error.
+#[track_caller]
+pub fn assert_decode_error<T, Ctx>(bytes: &[u8], ctx: Ctx, kind: ErrorKind) -> Error
+where
+    T: Decode<Ctx> + Debug,
+{
+    match crate::from_bytes_with_context::<T, Ctx>(bytes, ctx) {
+        Ok(value) => panic!(
+            "expected an error of kind {:?}, but decoded {:?}",
+            kind, value
+        ),
+        Err(error) if error.kind() != kind => panic!(
+            "expected an error of kind {:?}, got {:?}: {}",
+            kind,
+            error.kind(),
+            Chain(&error)
+        ),
+        Err(error) => error,
+    }
+}
+
+/// Decodes every file in the directory `dir` as a `T`, and asserts that each value encodes back to
+/// exactly the same bytes. Returns the number of files that were checked.
+///
+/// Subdirectories are ignored. Each file must be consumed entirely, like with
+/// [`from_bytes`](crate::from_bytes).
+///
+/// # Panics
+///
+/// Panics if the directory can't be read or has no files, or if any file fails to decode,
+/// re-encode, or re-encodes to different bytes. All files are checked before panicking, and all
+/// failures are listed.
+#[track_caller]
+pub fn assert_golden_dir<T, Ctx>(dir: impl AsRef<Path>, ctx: Ctx) -> usize
+where
+    T: Encode<Ctx> + Decode<Ctx>,
+    Ctx: Clone,
+{
+    let dir = dir.as_ref();
+    let mut paths = Vec::new();
+    let entries = fs::read_dir(dir)
+        .unwrap_or_else(|error| panic!("failed to read {}: {}", dir.display(), error));
+    for entry in entries {
+        let entry =
+            entry.unwrap_or_else(|error| panic!("failed to read {}: {}", dir.display(), error));
+        if entry.path().is_file() {
+            paths.push(entry.path());
+        }
+    }
+    // Sort for a stable order of failures.
+    paths.sort();
+    if paths.is_empty() {
+        panic!("no samples found in {}", dir.display());
+    }
+
+    let mut failures = String::new();
+    for path in &paths {
+        let bytes = fs::read(path)
+            .unwrap_or_else(|error| panic!("failed to read {}: {}", path.display(), error));
+        let result = crate::from_bytes_with_context::<T, Ctx>(&bytes, ctx.clone())
+            .map_err(|error| format!("failed to decode: {}", Chain(&error)))
+            .and_then(|value| {
+                crate::to_bytes_with_context(&value, ctx.clone())
+                    .map_err(|error| format!("failed to encode: {}", Chain(&error)))
+            })
+            .and_then(|encoded| match mismatch(&bytes, &encoded) {
+                Some(mismatch) => Err(format!("re-encoding gave different bytes\n{}", mismatch)),
+                None => Ok(()),
+            });
+        if let Err(message) = result {
+            writeln!(failures, "{}: {}", path.display(), message).unwrap();
+        }
+    }
+    if !failures.is_empty() {
+        panic!(
+            "golden files in {} did not round-trip:\n{}",
+            dir.display(),
+            failures
+        );
+    }
+    paths.len()
+}
+
+/// Describes where `actual` first differs from `expected`, if it does.
+fn mismatch(expected: &[u8], actual: &[u8]) -> Option<String> {
+    let offset = match expected.iter().zip(actual).position(|(a, b)| a != b) {
+        Some(offset) => offset,
+        None if expected.len() == actual.len() => return None,
+        None => expected.len().min(actual.len()),
+    };
+    Some(format!(
+        "  first difference at offset {:#x}\n  expected: {}\n    actual: {}",
+        offset,
+        Hex(expected),
+        Hex(actual)
+    ))
+}
+
+/// Formats bytes as space-separated hex.
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, "] ({} bytes)", self.0.len())
+    }
+}
+
+/// Formats an error with all of its sources.
+struct Chain<'a>(&'a Error);
+
+impl fmt::Display for Chain<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut message = self.0.to_string();
+        write!(f, "{}", message)?;
+        let mut source = std::error::Error::source(self.0);
+        while let Some(error) = source {
+            // Wrapped errors have the same message as their source.
+            let next = error.to_string();
+            if next != message {
+                write!(f, ": {}", next)?;
+            }
+            message = next;
+            source = error.source();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,552 @@
+//! Tracing of decoded values, for diagnosing mis-parsed data.
+//!
+//! Decoding with the functions in this module records a [`Trace`]: a tree of [`Span`]s, one for
+//! each field decoded by a derived `Decode` implementation, with the range of bytes it occupies
+//! and its value. When decoding fails, the trace still contains everything decoded up to the
+//! failure, and the span that failed is marked as incomplete.
+//!
+//! Values are recorded with their `Debug` implementation, if the field type has one. Fields with
+//! generic types are only recorded if the derived impl's bounds require `Debug`.
+//!
+//! # Example
+//!
+//! ```
+//! use declio::{trace, Decode};
+//! use declio::ctx::Len;
+//!
+//! #[derive(Debug, Decode)]
+//! #[declio(endian = "big")]
+//! struct Message {
+//!     kind: u8,
+//!     len: u16,
+//!     #[declio(ctx = "Len(*len as usize)")]
+//!     payload: Vec<u8>,
+//! }
+//!
+//! let bytes = [0x01, 0x00, 0x04, 0xaa, 0xbb];
+//! let (result, trace) = trace::from_bytes::<Message>(&bytes);
+//! assert!(result.is_err());
+//!
+//! let failed = trace.failed().unwrap();
+//! assert_eq!(failed.path, "payload");
+//! assert_eq!((failed.start, failed.end), (3, 5));
+//!
+//! print!("{}", trace.hex_dump(&bytes));
+//! ```
+//!
+//! This prints the following dump:
+//!
+//! ```text
+//! 00000000  01                                               kind = 1
+//! 00000001  00 04                                            len = 4
+//! 00000003  aa bb                                            payload: error: failed to fill whole buffer
+//! ```
+
+use crate::{Decode, Error};
+use std::cell::{Cell, RefCell};
+use std::fmt::{self, Write};
+use std::io;
+
+/// Decodes a value from a reader with the given context, recording a trace.
+///
+/// Offsets in the trace are relative to the position of the reader when this is called.
+pub fn decode<T, Ctx, R>(ctx: Ctx, reader: &mut R) -> (Result<T, Error>, Trace)
+where
+    T: Decode<Ctx>,
+    R: io::Read,
+{
+    struct Restore(Option<Tracer>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            replace_tracer(self.0.take());
+        }
+    }
+
+    let restore = Restore(replace_tracer(Some(Tracer::default())));
+    let result = T::decode(ctx, &mut TracingReader(reader));
+    let tracer = replace_tracer(None).unwrap_or_default();
+    drop(restore);
+
+    let mut trace = Trace::new(tracer.roots);
+    if let Err(error) = &result {
+        if let Some(span) = trace.failed_mut() {
+            span.error = Some(root_cause(error));
+        }
+    }
+    (result, trace)
+}
+
+/// Decodes a value from a byte slice, recording a trace.
+///
+/// Like [`from_bytes`](crate::from_bytes), it is an error if the slice is not consumed entirely.
+pub fn from_bytes<T>(bytes: &[u8]) -> (Result<T, Error>, Trace)
+where
+    T: Decode,
+{
+    from_bytes_with_context(bytes, ())
+}
+
+/// Decodes a value from a byte slice with the given context, recording a trace.
+///
+/// Like [`from_bytes_with_context`](crate::from_bytes_with_context), it is an error if the slice
+/// is not consumed entirely.
+pub fn from_bytes_with_context<T, Ctx>(mut bytes: &[u8], ctx: Ctx) -> (Result<T, Error>, Trace)
+where
+    T: Decode<Ctx>,
+{
+    let (result, trace) = decode(ctx, &mut bytes);
+    let result = result.and_then(|value| {
+        if bytes.is_empty() {
+            Ok(value)
+        } else {
+            Err(Error::trailing_bytes())
+        }
+    });
+    (result, trace)
+}
+
+/// A tree of spans recorded while decoding.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trace {
+    spans: Vec<Span>,
+}
+
+/// A value decoded by a derived `Decode` implementation, or one of its fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    /// Whether the span is a field or a whole value.
+    pub kind: SpanKind,
+    /// The name of the field, or of the type for [`SpanKind::Type`].
+    pub name: &'static str,
+    /// The path of the field from the decoded value, like `header.entries[2].len`.
+    pub path: String,
+    /// The name of the variant that was decoded, for enums.
+    pub variant: Option<&'static str>,
+    /// The offset of the first byte of the span.
+    pub start: u64,
+    /// The offset after the last byte of the span.
+    pub end: u64,
+    /// The decoded value, formatted with `Debug`, if its type implements it.
+    pub value: Option<String>,
+    /// Whether the span was decoded successfully.
+    pub complete: bool,
+    /// The error that caused decoding to fail, for the innermost incomplete span.
+    pub error: Option<String>,
+    /// The spans decoded as part of this one, in order.
+    pub children: Vec<Span>,
+}
+
+/// The kind of a [`Span`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    /// A value of a type with a derived `Decode` implementation. It does not contribute to paths.
+    Type,
+    /// A field of a struct or enum variant.
+    Field,
+    /// The variant ID of an enum.
+    Id,
+}
+
+impl Trace {
+    fn new(mut spans: Vec<Span>) -> Self {
+        set_paths(&mut spans, "");
+        Self { spans }
+    }
+
+    /// The top-level spans, usually a single [`SpanKind::Type`] span for the decoded value.
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    /// The innermost span that was not decoded successfully, if decoding failed.
+    pub fn failed(&self) -> Option<&Span> {
+        let mut failed = self.spans.iter().find(|span| !span.complete)?;
+        while let Some(child) = failed.children.iter().find(|span| !span.complete) {
+            failed = child;
+        }
+        Some(failed)
+    }
+
+    fn failed_mut(&mut self) -> Option<&mut Span> {
+        let mut failed = self.spans.iter_mut().find(|span| !span.complete)?;
+        while failed.children.iter().any(|span| !span.complete) {
+            failed = failed
+                .children
+                .iter_mut()
+                .find(|span| !span.complete)
+                .unwrap();
+        }
+        Some(failed)
+    }
+
+    /// The spans which don't contain other fields, in order. These cover the decoded bytes at the
+    /// finest granularity.
+    pub fn leaves(&self) -> Vec<&Span> {
+        fn collect<'a>(spans: &'a [Span], leaves: &mut Vec<&'a Span>) {
+            for span in spans {
+                let len = leaves.len();
+                collect(&span.children, leaves);
+                if leaves.len() == len && span.kind != SpanKind::Type {
+                    leaves.push(span);
+                }
+            }
+        }
+
+        let mut leaves = Vec::new();
+        collect(&self.spans, &mut leaves);
+        leaves
+    }
+
+    /// Renders a hex dump of the traced bytes, annotated with the path and value of each leaf
+    /// span.
+    ///
+    /// Each leaf starts a new line, with its offset. Bytes that are not covered by any leaf are
+    /// also shown, including those after the end of the trace. If decoding failed outside of a
+    /// leaf, like on an unknown enum ID, the error is shown on the last line.
+    pub fn hex_dump(&self, bytes: &[u8]) -> String {
+        let mut out = String::new();
+        let mut pos = 0;
+        let leaves = self.leaves();
+        for leaf in &leaves {
+            let start = (leaf.start as usize).min(bytes.len());
+            let end = (leaf.end as usize).clamp(start, bytes.len());
+            if start > pos {
+                write_dump_lines(&mut out, bytes, pos, start, "(not traced)");
+            }
+            write_dump_lines(&mut out, bytes, start, end, &leaf.annotation());
+            pos = pos.max(end);
+        }
+        if pos < bytes.len() {
+            write_dump_lines(&mut out, bytes, pos, bytes.len(), "(not decoded)");
+        }
+        // Errors of leaves are shown with their bytes, but others need a line of their own.
+        if let Some(failed) = self.failed() {
+            if let (Some(error), false) = (&failed.error, leaves.contains(&failed)) {
+                match failed.path.as_str() {
+                    "" => writeln!(out, "error: {}", error).unwrap(),
+                    path => writeln!(out, "error in {}: {}", path, error).unwrap(),
+                }
+            }
+        }
+        out
+    }
+}
+
+impl Span {
+    fn annotation(&self) -> String {
+        let mut annotation = self.path.clone();
+        if let Some(variant) = self.variant {
+            write!(annotation, " ({})", variant).unwrap();
+        }
+        if let Some(error) = &self.error {
+            write!(annotation, ": error: {}", error).unwrap();
+        } else if !self.complete {
+            annotation.push_str(": incomplete");
+        } else if let Some(value) = &self.value {
+            write!(annotation, " = {}", truncate(value)).unwrap();
+        }
+        annotation
+    }
+}
+
+impl fmt::Display for Trace {
+    /// Formats the span tree, with one span per line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn write_spans(f: &mut fmt::Formatter, spans: &[Span], depth: usize) -> fmt::Result {
+            for span in spans {
+                let name = match span.kind {
+                    SpanKind::Type => span.name.to_string(),
+                    _ => span.annotation(),
+                };
+                writeln!(
+                    f,
+                    "{:indent$}{} [{}..{}]",
+                    "",
+                    name,
+                    span.start,
+                    span.end,
+                    indent = depth * 2
+                )?;
+                write_spans(f, &span.children, depth + 1)?;
+            }
+            Ok(())
+        }
+
+        write_spans(f, &self.spans, 0)
+    }
+}
+
+const BYTES_PER_LINE: usize = 16;
+const MAX_VALUE_LEN: usize = 64;
+
+fn write_dump_lines(out: &mut String, bytes: &[u8], start: usize, end: usize, annotation: &str) {
+    let mut line_start = start;
+    loop {
+        let line_end = end.min(line_start + BYTES_PER_LINE);
+        let hex: Vec<String> = bytes[line_start..line_end]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        let annotation = if line_start == start { annotation } else { "" };
+        let line = format!(
+            "{:08x}  {:width$}  {}",
+            line_start,
+            hex.join(" "),
+            annotation,
+            width = BYTES_PER_LINE * 3 - 1,
+        );
+        writeln!(out, "{}", line.trim_end()).unwrap();
+        line_start = line_end;
+        if line_start >= end {
+            break;
+        }
+    }
+}
+
+fn truncate(value: &str) -> String {
+    match value.char_indices().nth(MAX_VALUE_LEN) {
+        Some((index, _)) => format!("{}...", &value[..index]),
+        None => value.to_string(),
+    }
+}
+
+/// Computes the paths of spans. Type spans are transparent, except that they are indexed when a
+/// field contains more than one of them, like the elements of a `Vec`.
+fn set_paths(spans: &mut [Span], prefix: &str) {
+    let types = spans
+        .iter()
+        .filter(|span| span.kind == SpanKind::Type)
+        .count();
+    let mut index = 0;
+    for span in spans {
+        span.path = match span.kind {
+            SpanKind::Type if types > 1 => {
+                index += 1;
+                format!("{}[{}]", prefix, index - 1)
+            }
+            SpanKind::Type => prefix.to_string(),
+            SpanKind::Field | SpanKind::Id if prefix.is_empty() => span.name.to_string(),
+            SpanKind::Field | SpanKind::Id => format!("{}.{}", prefix, span.name),
+        };
+        let path = span.path.clone();
+        set_paths(&mut span.children, &path);
+    }
+}
+
+fn root_cause(error: &Error) -> String {
+    let mut error: &(dyn std::error::Error + 'static) = error;
+    while let Some(source) = error.source() {
+        error = source;
+    }
+    error.to_string()
+}
+
+#[derive(Default)]
+struct Tracer {
+    pos: u64,
+    stack: Vec<Span>,
+    roots: Vec<Span>,
+}
+
+impl Tracer {
+    fn close(&mut self, depth: usize, value: Option<String>, complete: bool) {
+        while self.stack.len() > depth {
+            let mut span = self.stack.pop().unwrap();
+            span.end = self.pos;
+            // Only the span being closed is complete; any left open inside it have failed.
+            if self.stack.len() == depth {
+                span.value = value.clone();
+                span.complete = complete;
+            }
+            match self.stack.last_mut() {
+                Some(parent) => parent.children.push(span),
+                None => self.roots.push(span),
+            }
+        }
+    }
+}
+
+thread_local! {
+    static TRACER: RefCell<Option<Tracer>> = const { RefCell::new(None) };
+    /// Whether `TRACER` is set, so that decoding without a trace doesn't need to borrow it.
+    static ACTIVE: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Replaces the current tracer, returning the previous one.
+fn replace_tracer(tracer: Option<Tracer>) -> Option<Tracer> {
+    ACTIVE.with(|active| active.set(tracer.is_some()));
+    TRACER.with(|current| current.replace(tracer))
+}
+
+/// Whether a trace is being recorded. Derived decoders check this once, and pass it to each
+/// [`SpanGuard`], so that they don't touch the tracer at all otherwise.
+#[doc(hidden)]
+#[inline]
+pub fn active() -> bool {
+    ACTIVE.with(Cell::get)
+}
+
+/// Calls `f` with the current tracer, if a trace is being recorded.
+fn with_tracer<F, T>(f: F) -> Option<T>
+where
+    F: FnOnce(&mut Tracer) -> T,
+{
+    if !active() {
+        return None;
+    }
+    TRACER.with(|tracer| tracer.borrow_mut().as_mut().map(f))
+}
+
+struct TracingReader<'a, R>(&'a mut R);
+
+impl<R> io::Read for TracingReader<'_, R>
+where
+    R: io::Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.0.read(buf)?;
+        with_tracer(|tracer| tracer.pos += len as u64);
+        Ok(len)
+    }
+}
+
+/// Bytes that were read ahead of a decoder, and are given back to it from a buffer.
+///
+/// Creating it moves the position of the trace back over the bytes, and reading them moves it
+/// forward again, so that the spans decoded from them start at the right offset.
+pub(crate) struct Unread<'a>(&'a [u8]);
+
+impl<'a> Unread<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        with_tracer(|tracer| tracer.pos = tracer.pos.saturating_sub(bytes.len() as u64));
+        Self(bytes)
+    }
+
+    /// Whether all of the bytes have been read again.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The number of bytes that have not been read again yet.
+    #[cfg(feature = "bytes")]
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Drops the bytes that have not been read again, for when the reader they were read ahead
+    /// from is moved back over them instead.
+    #[cfg(feature = "bytes")]
+    pub(crate) fn clear(&mut self) {
+        self.0 = &[];
+    }
+}
+
+impl io::Read for Unread<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.0.read(buf)?;
+        with_tracer(|tracer| tracer.pos += len as u64);
+        Ok(len)
+    }
+}
+
+/// Records a span while it is alive. Used by the derive macros.
+#[doc(hidden)]
+pub struct SpanGuard {
+    depth: Option<usize>,
+}
+
+impl SpanGuard {
+    /// Opens a span, if `tracing` is set and a trace is being recorded.
+    #[inline]
+    pub fn enter(tracing: bool, kind: SpanKind, name: &'static str) -> Self {
+        if !tracing {
+            return Self { depth: None };
+        }
+        let depth = with_tracer(|tracer| {
+            let depth = tracer.stack.len();
+            tracer.stack.push(Span {
+                kind,
+                name,
+                path: String::new(),
+                variant: None,
+                start: tracer.pos,
+                end: tracer.pos,
+                value: None,
+                complete: false,
+                error: None,
+                children: Vec::new(),
+            });
+            depth
+        });
+        Self { depth }
+    }
+
+    /// Records the variant of an enum.
+    pub fn variant(&self, variant: &'static str) {
+        if let Some(depth) = self.depth {
+            with_tracer(|tracer| {
+                if let Some(span) = tracer.stack.get_mut(depth) {
+                    span.variant = Some(variant);
+                }
+            });
+        }
+    }
+
+    /// Closes the span successfully, recording the value returned by `value`, which is only
+    /// called if a trace is being recorded.
+    pub fn exit<F>(mut self, value: F)
+    where
+        F: FnOnce() -> Option<String>,
+    {
+        if let Some(depth) = self.depth.take() {
+            let value = value();
+            with_tracer(|tracer| tracer.close(depth, value, true));
+        }
+    }
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        if let Some(depth) = self.depth {
+            with_tracer(|tracer| tracer.close(depth, None, false));
+        }
+    }
+}
+
+/// Wraps a value for [`TraceDebug`], which formats it if it implements `Debug`. Used by the
+/// derive macros.
+///
+/// Calling `(&DebugValue(&value)).trace_debug()` with both traits in scope resolves to
+/// [`TraceDebug`] if the type implements `Debug`, since it matches without an extra reference,
+/// and to [`TraceNoDebug`] otherwise.
+#[doc(hidden)]
+pub struct DebugValue<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait TraceDebug {
+    fn trace_debug(&self) -> Option<String>;
+}
+
+impl<T> TraceDebug for DebugValue<'_, T>
+where
+    T: fmt::Debug + ?Sized,
+{
+    fn trace_debug(&self) -> Option<String> {
+        Some(format!("{:?}", self.0))
+    }
+}
+
+#[doc(hidden)]
+pub trait TraceNoDebug {
+    fn trace_debug(&self) -> Option<String>;
+}
+
+impl<T> TraceNoDebug for &DebugValue<'_, T>
+where
+    T: ?Sized,
+{
+    fn trace_debug(&self) -> Option<String> {
+        None
+    }
+}
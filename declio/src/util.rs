@@ -1,10 +1,73 @@
 //! Utilities that aren't part of the "core" of declio, but may be useful in reducing boilerplate.
 
-use crate::ctx::{Endian, Len};
+use crate::ctx::{Endian, Len, ToEnd, Until};
 use crate::{Decode, Encode, Error};
 
 #[doc(inline)]
-pub use crate::magic_bytes;
+pub use crate::{endian_marker, magic_bytes};
+
+use std::io;
+
+/// Decodes a value that occupies exactly `len` bytes of the stream.
+///
+/// The `decode` function is given a sub-reader that ends after `len` bytes, so the value can't
+/// read any further than that. If the value does not consume all of its bytes, this returns an
+/// error, unless `pad` is set, in which case the remaining bytes are skipped.
+///
+/// This is used to implement the `byte_len` attribute in derive macros.
+pub fn decode_byte_len<T, R, F>(
+    len: usize,
+    pad: bool,
+    reader: &mut R,
+    decode: F,
+) -> Result<T, Error>
+where
+    R: io::Read,
+    F: FnOnce(&mut io::Take<&mut R>) -> Result<T, Error>,
+{
+    let mut sub_reader = io::Read::take(reader, len as u64);
+    let value = decode(&mut sub_reader)?;
+    if sub_reader.limit() > 0 {
+        if !pad {
+            return Err(Error::new(format!(
+                "{} of {} bytes were not consumed",
+                sub_reader.limit(),
+                len
+            )));
+        }
+        io::copy(&mut sub_reader, &mut io::sink())?;
+        if sub_reader.limit() > 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+    }
+    Ok(value)
+}
+
+/// Encodes a value that occupies exactly `len` bytes of the stream.
+///
+/// The `encode` function writes into a buffer, which is checked against `len` before it is
+/// written. If the value is larger than `len`, this returns an error. If the value is smaller,
+/// this returns an error, unless `pad` is set, in which case the remainder is filled with zeros.
+///
+/// This is used to implement the `byte_len` attribute in derive macros.
+pub fn encode_byte_len<W, F>(len: usize, pad: bool, writer: &mut W, encode: F) -> Result<(), Error>
+where
+    W: io::Write,
+    F: FnOnce(&mut Vec<u8>) -> Result<(), Error>,
+{
+    let mut buf = Vec::new();
+    encode(&mut buf)?;
+    if buf.len() > len || (buf.len() < len && !pad) {
+        return Err(Error::new(format!(
+            "encoded size {} does not match the byte length {}",
+            buf.len(),
+            len
+        )));
+    }
+    buf.resize(len, 0);
+    writer.write_all(&buf)?;
+    Ok(())
+}
 
 macro_rules! endian_wrappers {
     ($($(#[$attr:meta])* $name:ident: $endian:expr,)*) => {$(
@@ -163,10 +226,74 @@ pub mod utf8 {
     }
 }
 
+/// Helper module for arrays whose elements each need a different context, for use in derive
+/// macros.
+///
+/// Instead of a single context shared by all of the elements, the context is a function that is
+/// called with the index of each element and returns the context for that element.
+///
+/// # Examples
+///
+/// ```
+/// use declio::{Encode, Decode};
+/// use declio::ctx::Endian;
+/// use declio::util::per_index;
+///
+/// #[derive(Debug, PartialEq, Encode, Decode)]
+/// struct Mixed {
+///     // The first element is big-endian, and the rest are little-endian.
+///     #[declio(
+///         with = "per_index",
+///         ctx = "|i| if i == 0 { Endian::Big } else { Endian::Little }"
+///     )]
+///     words: [u16; 3],
+/// }
+///
+/// let value = Mixed { words: [0x0102, 0x0304, 0x0506] };
+///
+/// let bytes = declio::to_bytes(&value).unwrap();
+/// assert_eq!(bytes, [0x01, 0x02, 0x04, 0x03, 0x06, 0x05]);
+///
+/// let decoded: Mixed = declio::from_bytes(&bytes).unwrap();
+/// assert_eq!(decoded, value);
+/// ```
+pub mod per_index {
+    use crate::{Decode, Encode, Error};
+
+    #[allow(missing_docs)]
+    pub fn encode<T, Ctx, F, W, const N: usize>(
+        arr: &[T; N],
+        mut ctx: F,
+        writer: &mut W,
+    ) -> Result<(), Error>
+    where
+        T: Encode<Ctx>,
+        F: FnMut(usize) -> Ctx,
+        W: std::io::Write,
+    {
+        for (index, elem) in arr.iter().enumerate() {
+            elem.encode(ctx(index), writer)?;
+        }
+        Ok(())
+    }
+
+    #[allow(missing_docs)]
+    pub fn decode<T, Ctx, F, R, const N: usize>(mut ctx: F, reader: &mut R) -> Result<[T; N], Error>
+    where
+        T: Decode<Ctx>,
+        F: FnMut(usize) -> Ctx,
+        R: std::io::Read,
+    {
+        crate::try_array(|index| T::decode(ctx(index), reader))
+    }
+}
+
 /// UTF-8 wrapper type for strings.
 ///
 /// Encodes and decodes strings as a UTF-8 byte string. Like other sequence types, decoding
-/// requires a [`Len`] context value, to specify how many bytes should be read.
+/// requires a [`Len`] context value, to specify how many bytes should be read. Alternatively,
+/// [`ToEnd`] reads the rest of the stream, and [`Until`] reads up to a terminating byte, like
+/// `Until(0)` for null-terminated strings.
 ///
 /// # Examples
 ///
@@ -220,6 +347,24 @@ impl Encode<()> for Utf8 {
     }
 }
 
+impl Encode<ToEnd> for Utf8 {
+    fn encode<W>(&self, ctx: ToEnd, writer: &mut W) -> Result<(), Error>
+    where
+        W: std::io::Write,
+    {
+        utf8::encode(&self.0, ctx, writer)
+    }
+}
+
+impl Encode<Until<u8>> for Utf8 {
+    fn encode<W>(&self, ctx: Until<u8>, writer: &mut W) -> Result<(), Error>
+    where
+        W: std::io::Write,
+    {
+        utf8::encode(&self.0, ctx, writer)
+    }
+}
+
 impl Decode<Len> for Utf8 {
     fn decode<R>(ctx: Len, reader: &mut R) -> Result<Self, Error>
     where
@@ -229,6 +374,24 @@ impl Decode<Len> for Utf8 {
     }
 }
 
+impl Decode<ToEnd> for Utf8 {
+    fn decode<R>(ctx: ToEnd, reader: &mut R) -> Result<Self, Error>
+    where
+        R: std::io::Read,
+    {
+        utf8::decode(ctx, reader).map(Self)
+    }
+}
+
+impl Decode<Until<u8>> for Utf8 {
+    fn decode<R>(ctx: Until<u8>, reader: &mut R) -> Result<Self, Error>
+    where
+        R: std::io::Read,
+    {
+        utf8::decode(ctx, reader).map(Self)
+    }
+}
+
 impl From<String> for Utf8 {
     fn from(value: String) -> Self {
         Self(value)
@@ -273,6 +436,7 @@ impl From<Utf8> for String {
 /// assert_eq!(decoded, value);
 /// ```
 pub mod zero_one {
+    use crate::ctx::BoolRepr;
     use crate::{Decode, Encode, Error};
 
     #[allow(missing_docs)]
@@ -280,11 +444,7 @@ pub mod zero_one {
     where
         W: std::io::Write,
     {
-        let byte: u8 = match b {
-            false => 0,
-            true => 1,
-        };
-        byte.encode((), writer)
+        b.encode(BoolRepr::ZeroOne, writer)
     }
 
     #[allow(missing_docs)]
@@ -292,22 +452,15 @@ pub mod zero_one {
     where
         R: std::io::Read,
     {
-        let byte: u8 = Decode::decode((), reader)?;
-        match byte {
-            0 => Ok(false),
-            1 => Ok(true),
-            _ => Err(Error::new(format!(
-                "invalid byte value for boolean: expected 0 or 1, got {:?}",
-                byte
-            ))),
-        }
+        bool::decode(BoolRepr::ZeroOne, reader)
     }
 }
 
 /// Zero-one wrapper type for booleans.
 ///
 /// Encodes and decodes booleans as a single byte, either a zero `0` for `false`, or a one `1` for
-/// true.
+/// true. This is equivalent to passing [`BoolRepr::ZeroOne`](crate::ctx::BoolRepr::ZeroOne) as
+/// context to `bool`.
 ///
 /// # Examples
 ///
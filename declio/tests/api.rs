@@ -0,0 +1,104 @@
+use declio::ctx::{Endian, Len};
+use declio::{Decode, Encode, ErrorKind};
+use std::io::{self, Read};
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "big")]
+struct Message {
+    len: u8,
+    #[declio(ctx = "Len(*len as usize)")]
+    payload: Vec<u8>,
+}
+
+fn message() -> Message {
+    Message {
+        len: 2,
+        payload: vec![0xaa, 0xbb],
+    }
+}
+
+#[test]
+fn to_writer() {
+    let mut bytes = Vec::new();
+    declio::to_writer(message(), &mut bytes).unwrap();
+    declio::to_writer_with_context(0x1234u16, Endian::Little, &mut bytes).unwrap();
+    assert_eq!(bytes, [0x02, 0xaa, 0xbb, 0x34, 0x12]);
+}
+
+#[test]
+fn to_slice() {
+    let mut buf = [0u8; 8];
+    assert_eq!(declio::to_slice(message(), &mut buf).unwrap(), 3);
+    assert_eq!(buf[..3], [0x02, 0xaa, 0xbb]);
+
+    assert_eq!(
+        declio::to_slice_with_context(0x1234u16, Endian::Big, &mut buf[..2]).unwrap(),
+        2
+    );
+    assert_eq!(buf[..2], [0x12, 0x34]);
+
+    // The value does not fit.
+    assert!(declio::to_slice(message(), &mut buf[..2]).is_err());
+}
+
+#[test]
+fn from_bytes_prefix() {
+    let bytes = [0x02, 0xaa, 0xbb, 0xcc];
+    let (value, rest) = declio::from_bytes_prefix::<Message>(&bytes).unwrap();
+    assert_eq!(value, message());
+    assert_eq!(rest, [0xcc]);
+
+    let (value, rest) =
+        declio::from_bytes_prefix_with_context::<u16, _>(&bytes, Endian::Big).unwrap();
+    assert_eq!(value, 0x02aa);
+    assert_eq!(rest, [0xbb, 0xcc]);
+}
+
+#[test]
+fn from_reader() {
+    let mut reader = io::Cursor::new([0x02, 0xaa, 0xbb, 0x12, 0x34]);
+    assert_eq!(
+        declio::from_reader::<Message, _>(&mut reader).unwrap(),
+        message()
+    );
+    assert_eq!(
+        declio::from_reader_with_context::<u16, _, _>(&mut reader, Endian::Big).unwrap(),
+        0x1234
+    );
+    assert_eq!(reader.position(), 5);
+
+    let error = declio::from_reader::<Message, _>(&[0x02, 0xaa][..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn from_reader_to_end() {
+    assert_eq!(
+        declio::from_reader_to_end::<Message, _>(&[0x02, 0xaa, 0xbb][..]).unwrap(),
+        message()
+    );
+
+    let mut reader = &[0x12, 0x34, 0x56, 0x78][..];
+    let error =
+        declio::from_reader_to_end_with_context::<u16, _, _>(&mut reader, Endian::Big).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::TrailingBytes);
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, [0x78]);
+}
+
+#[test]
+fn trailing_bytes() {
+    let bytes = [0x02, 0xaa, 0xbb, 0xcc];
+    let errors = [
+        declio::from_bytes::<Message>(&bytes).unwrap_err(),
+        declio::from_reader_to_end::<Message, _>(&bytes[..]).unwrap_err(),
+        declio::from_bytes_with_limits::<Message, _>(&bytes, (), declio::Limits::default())
+            .unwrap_err(),
+        declio::trace::from_bytes::<Message>(&bytes).0.unwrap_err(),
+    ];
+    for error in &errors {
+        assert_eq!(error.kind(), ErrorKind::TrailingBytes);
+        assert_eq!(error.to_string(), "input was not fully consumed");
+    }
+}
@@ -0,0 +1,177 @@
+use bytes::{Buf, Bytes, BytesMut};
+use declio::ctx::{Len, ToEnd};
+use declio::{Decode, Encode, ErrorKind, Limits};
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "big")]
+struct Packet {
+    len: u16,
+    #[declio(ctx = "Len(*len as usize)")]
+    payload: Bytes,
+    #[declio(ctx = "ToEnd")]
+    trailer: Bytes,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct Framed {
+    size: u8,
+    #[declio(byte_len = "*size as usize")]
+    packet: Packet,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct Chunk {
+    len: u8,
+    #[declio(ctx = "Len(*len as usize)")]
+    data: Bytes,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct FramedChunks {
+    size: u8,
+    #[declio(ctx = "ToEnd", byte_len = "*size as usize")]
+    chunks: Vec<Chunk>,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct Nested {
+    size: u8,
+    #[declio(byte_len = "*size as usize")]
+    framed: FramedChunks,
+}
+
+/// Returns whether `slice` points into the memory of `bytes`.
+fn shares(slice: &Bytes, bytes: &Bytes) -> bool {
+    let range = bytes.as_ptr_range();
+    slice.is_empty() || (range.start <= slice.as_ptr() && slice.as_ptr() < range.end)
+}
+
+#[test]
+fn decode_buf() {
+    let mut buf = BytesMut::from(&[0x12, 0x34, 0x56][..]);
+    let value: u16 = declio::decode_buf(&mut buf, declio::ctx::Endian::Big).unwrap();
+    assert_eq!(value, 0x1234);
+    assert_eq!(buf[..], [0x56]);
+
+    // Buffers don't need to be contiguous.
+    let mut buf = (&[0x00, 0x02, 0xaa][..]).chain(&[0xbb, 0xcc][..]);
+    let packet: Packet = declio::decode_buf(&mut buf, ()).unwrap();
+    assert_eq!(packet.payload, [0xaa, 0xbb][..]);
+    assert_eq!(packet.trailer, [0xcc][..]);
+    assert!(!buf.has_remaining());
+}
+
+#[test]
+fn encode_buf() {
+    let packet = Packet {
+        len: 2,
+        payload: Bytes::from_static(&[0xaa, 0xbb]),
+        trailer: Bytes::from_static(&[0xcc]),
+    };
+    let mut buf = BytesMut::new();
+    declio::encode_buf(&packet, (), &mut buf).unwrap();
+    assert_eq!(buf[..], [0x00, 0x02, 0xaa, 0xbb, 0xcc]);
+
+    let mut array = [0u8; 4];
+    let error = declio::encode_buf(&packet, (), &mut &mut array[..]).unwrap_err();
+    assert_eq!(error.to_string(), "error encoding field trailer");
+    assert_eq!(array, [0x00, 0x02, 0xaa, 0xbb]);
+}
+
+#[test]
+fn decode_bytes() {
+    let input = Bytes::from_static(&[0x00, 0x02, 0xaa, 0xbb, 0xcc, 0xdd]);
+    let mut bytes = input.clone();
+    let packet: Packet = declio::decode_bytes(&mut bytes, ()).unwrap();
+    assert_eq!(packet.payload, [0xaa, 0xbb][..]);
+    assert_eq!(packet.trailer, [0xcc, 0xdd][..]);
+    assert!(shares(&packet.payload, &input));
+    assert!(shares(&packet.trailer, &input));
+    assert!(bytes.is_empty());
+
+    // Also through the reader of a `byte_len` field.
+    let input = Bytes::from_static(&[0x04, 0x00, 0x01, 0xaa, 0xbb, 0xff]);
+    let mut bytes = input.clone();
+    let framed: Framed = declio::decode_bytes(&mut bytes, ()).unwrap();
+    assert_eq!(framed.packet.payload, [0xaa][..]);
+    assert_eq!(framed.packet.trailer, [0xbb][..]);
+    assert!(shares(&framed.packet.payload, &input));
+    assert!(shares(&framed.packet.trailer, &input));
+    assert_eq!(bytes, [0xff][..]);
+}
+
+#[test]
+fn decode_bytes_sequences() {
+    // Elements decoded until the end start with a byte that was read ahead.
+    let input = Bytes::from_static(&[0x01, 0x02, 0x03]);
+    let mut bytes = input.clone();
+    let elems: Vec<Bytes> = declio::decode_bytes(&mut bytes, (ToEnd, Len(1))).unwrap();
+    assert_eq!(elems, [&[0x01][..], &[0x02], &[0x03]]);
+    assert!(elems.iter().all(|elem| shares(elem, &input)));
+    assert!(bytes.is_empty());
+
+    let input = Bytes::from_static(&[0x02, 0xaa, 0xbb, 0x00, 0x01, 0xcc]);
+    let mut bytes = input.clone();
+    let chunks: Vec<Chunk> = declio::decode_bytes(&mut bytes, (ToEnd, ())).unwrap();
+    let data: Vec<&[u8]> = chunks.iter().map(|chunk| &chunk.data[..]).collect();
+    assert_eq!(data, [&[0xaa, 0xbb][..], &[], &[0xcc]]);
+    assert!(chunks.iter().all(|chunk| shares(&chunk.data, &input)));
+
+    // And inside of a `byte_len` field.
+    let input = Bytes::from_static(&[0x04, 0x01, 0xaa, 0x01, 0xbb, 0xff]);
+    let mut bytes = input.clone();
+    let framed: FramedChunks = declio::decode_bytes(&mut bytes, ()).unwrap();
+    assert_eq!(framed.chunks[1].data, [0xbb][..]);
+    assert!(shares(&framed.chunks[1].data, &input));
+    assert_eq!(bytes, [0xff][..]);
+}
+
+#[test]
+fn decode_bytes_copied() {
+    // Readers that are not from `decode_bytes` give a copy.
+    let input = Bytes::from_static(&[0x01, 0xaa]);
+    let mut reader = std::io::BufReader::new(&input[..]);
+    let chunk: Chunk = Decode::decode((), &mut reader).unwrap();
+    assert_eq!(chunk.data, [0xaa][..]);
+    assert!(!shares(&chunk.data, &input));
+
+    // So do readers from `decode_bytes` that are nested more deeply than the ones it recognizes,
+    // like a sequence inside of two `byte_len` fields.
+    let input = Bytes::from_static(&[0x05, 0x04, 0x01, 0xaa, 0x01, 0xbb, 0xff]);
+    let mut bytes = input.clone();
+    let nested: Nested = declio::decode_bytes(&mut bytes, ()).unwrap();
+    let data: Vec<&[u8]> = nested
+        .framed
+        .chunks
+        .iter()
+        .map(|chunk| &chunk.data[..])
+        .collect();
+    assert_eq!(data, [&[0xaa][..], &[0xbb]]);
+    assert!(nested
+        .framed
+        .chunks
+        .iter()
+        .all(|chunk| !shares(&chunk.data, &input)));
+    assert_eq!(bytes, [0xff][..]);
+
+    // Without `decode_bytes`, there is nothing to share.
+    let packet: Packet = declio::from_bytes(&[0x00, 0x01, 0xaa]).unwrap();
+    assert_eq!(packet.payload, [0xaa][..]);
+}
+
+#[test]
+fn decode_bytes_error() {
+    let mut bytes = Bytes::from_static(&[0x00, 0x03, 0xaa]);
+    let error = declio::decode_bytes::<Packet, _>(&mut bytes, ()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(bytes.len(), 3);
+
+    let limits = Limits {
+        max_len: 1,
+        ..Limits::default()
+    };
+    let error = limits
+        .scope(|| declio::decode_bytes::<Bytes, _>(&mut bytes, Len(2)))
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::LimitExceeded);
+}
@@ -0,0 +1,120 @@
+use bytes::BytesMut;
+use declio::codec::{DeclioCodec, LengthPrefix};
+use declio::ctx::{Endian, Len, ToEnd};
+use declio::{Decode, Encode};
+use tokio_util::codec::{Decoder, Encoder};
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "big")]
+struct Message {
+    len: u8,
+    #[declio(ctx = "Len(*len as usize)")]
+    payload: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct Trailer {
+    kind: u8,
+    #[declio(ctx = "ToEnd")]
+    rest: Vec<u8>,
+}
+
+fn message(payload: &[u8]) -> Message {
+    Message {
+        len: payload.len() as u8,
+        payload: payload.to_vec(),
+    }
+}
+
+#[test]
+fn self_delimiting() {
+    let mut codec = DeclioCodec::<Message>::new(());
+    let mut buf = BytesMut::new();
+    codec.encode(message(&[0xaa, 0xbb]), &mut buf).unwrap();
+    codec.encode(&message(&[]), &mut buf).unwrap();
+    assert_eq!(buf[..], [0x02, 0xaa, 0xbb, 0x00]);
+
+    // Bytes arrive one at a time.
+    let mut src = BytesMut::new();
+    let mut decoded = Vec::new();
+    for &byte in buf.iter() {
+        src.extend_from_slice(&[byte]);
+        while let Some(message) = codec.decode(&mut src).unwrap() {
+            decoded.push(message);
+        }
+    }
+    assert_eq!(decoded, [message(&[0xaa, 0xbb]), message(&[])]);
+    assert!(src.is_empty());
+
+    // More bytes are reserved for an incomplete message.
+    let mut src = BytesMut::from(&[0x10, 0xaa][..]);
+    assert_eq!(codec.decode(&mut src).unwrap(), None);
+    assert!(src.capacity() >= 17);
+}
+
+#[test]
+fn length_prefix() {
+    let mut codec =
+        DeclioCodec::<u16, _>::new(Endian::Little).length_prefix(LengthPrefix::U32(Endian::Big));
+    let mut buf = BytesMut::new();
+    codec.encode(0x1234, &mut buf).unwrap();
+    assert_eq!(buf[..], [0x00, 0x00, 0x00, 0x02, 0x34, 0x12]);
+
+    let mut src = BytesMut::from(&buf[..3]);
+    assert_eq!(codec.decode(&mut src).unwrap(), None);
+    src.extend_from_slice(&buf[3..5]);
+    assert_eq!(codec.decode(&mut src).unwrap(), None);
+    src.extend_from_slice(&buf[5..]);
+    src.extend_from_slice(&[0x00]);
+    assert_eq!(codec.decode(&mut src).unwrap(), Some(0x1234));
+    assert_eq!(src[..], [0x00]);
+
+    // The message must use the whole frame.
+    let mut src = BytesMut::from(&[0x00, 0x00, 0x00, 0x03, 0x34, 0x12, 0x00][..]);
+    assert!(codec.decode(&mut src).is_err());
+}
+
+#[test]
+fn to_end() {
+    let trailer = Trailer {
+        kind: 1,
+        rest: vec![0xaa, 0xbb, 0xcc],
+    };
+
+    // With a length prefix, the message waits for the rest of its frame.
+    let mut codec = DeclioCodec::<Trailer>::new(()).length_prefix(LengthPrefix::U8);
+    let mut buf = BytesMut::new();
+    codec.encode(&trailer, &mut buf).unwrap();
+    assert_eq!(buf[..], [0x04, 0x01, 0xaa, 0xbb, 0xcc]);
+    let mut src = BytesMut::from(&buf[..3]);
+    assert_eq!(codec.decode(&mut src).unwrap(), None);
+    src.extend_from_slice(&buf[3..]);
+    assert_eq!(codec.decode(&mut src).unwrap(), Some(trailer));
+
+    // Without one, it ends wherever the received bytes end, as documented.
+    let mut codec = DeclioCodec::<Trailer>::new(());
+    let mut src = BytesMut::from(&[0x01, 0xaa][..]);
+    let decoded = codec.decode(&mut src).unwrap().unwrap();
+    assert_eq!(decoded.rest, [0xaa]);
+}
+
+#[test]
+fn max_frame_len() {
+    let mut codec = DeclioCodec::<Message>::new(())
+        .length_prefix(LengthPrefix::U8)
+        .max_frame_len(2);
+    let mut buf = BytesMut::new();
+    assert!(codec.encode(message(&[1, 2]), &mut buf).is_err());
+    assert!(buf.is_empty());
+    assert!(codec.decode(&mut BytesMut::from(&[0x03][..])).is_err());
+
+    let mut codec = DeclioCodec::<Message>::new(()).max_frame_len(2);
+    assert!(codec
+        .decode(&mut BytesMut::from(&[0x05, 0x00][..]))
+        .is_err());
+
+    // Frames must also fit in the length prefix.
+    let mut codec = DeclioCodec::<Vec<u8>, _>::new(Len(256)).length_prefix(LengthPrefix::U8);
+    assert!(codec.encode(vec![0; 256], &mut buf).is_err());
+    assert!(buf.is_empty());
+}
@@ -77,6 +77,168 @@ struct SkipIf {
     y: Option<BigEndian<u32>>,
 }
 
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct ByteLen {
+    size: u8,
+    #[declio(byte_len = "*size as usize")]
+    inner: Struct,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct ByteLenPad {
+    #[declio(byte_len = "4", byte_len_pad)]
+    inner: BigEndian<u16>,
+    x: u8,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(id_type = "u8")]
+enum Tree {
+    #[declio(id = "0")]
+    Leaf,
+    #[declio(id = "1")]
+    Node(Box<Tree>, Box<Tree>),
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(pod = "ctx::Endian::Little")]
+struct Pod {
+    x: u8,
+    y: u32,
+    z: [i16; 2],
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(ctx = "endian: ctx::Endian", pod = "endian")]
+struct PodCtx(u16, f32);
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "little")]
+struct ContainerEndian {
+    x: u16,
+    y: [u32; 1],
+    #[declio(ctx = "ctx::Endian::Big")]
+    z: u16,
+    #[declio(endian = "big")]
+    w: u16,
+    v: BigEndian<u16>,
+    #[declio(endian)]
+    nested: ContainerCtx,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "ctx")]
+struct ContainerEndianCtx {
+    x: u16,
+    #[declio(endian)]
+    nested: ContainerCtx,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(ctx = "e: ctx::Endian, len: usize", endian = "e")]
+struct ContainerEndianBinding {
+    x: u16,
+    #[declio(ctx = "(ctx::Len(len), e)")]
+    y: Vec<u16>,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(id_type = "u16", endian = "little")]
+enum EndianEnum {
+    #[declio(id = "1")]
+    Foo(u16),
+}
+
+declio::util::endian_marker! {
+    PcapMagic { big: b"\xa1\xb2\xc3\xd4", little: b"\xd4\xc3\xb2\xa1" }
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian_from = "magic")]
+struct EndianFrom {
+    magic: PcapMagic,
+    version: u16,
+    #[declio(endian)]
+    nested: ContainerCtx,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct InferredBound<T> {
+    x: T,
+    #[declio(ctx = "ctx::Endian::Big")]
+    y: u16,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "big")]
+struct InferredEndianBound<T, U> {
+    #[declio(endian)]
+    x: T,
+    y: Option<U>,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(ctx = "len: usize", bound = "T: Encode + Decode")]
+struct ContainerBound<T> {
+    #[declio(ctx = "ctx::Len(len)")]
+    x: Vec<T>,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct FieldBound<T> {
+    has_next: u8,
+    #[declio(
+        skip_if = "*has_next == 0",
+        bound(encode = "T: Encode", decode = "T: Decode")
+    )]
+    next: Option<Box<FieldBound<T>>>,
+    value: T,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(ctx = "inherit")]
+struct Inherit {
+    x: u16,
+    y: [u32; 1],
+    #[declio(ctx = "ctx::Endian::Big")]
+    z: u16,
+    nested: ContainerCtx,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(ctx = "inherit", id_type = "u8")]
+enum InheritEnum<T> {
+    #[declio(id = "0")]
+    Unit,
+    #[declio(id = "1")]
+    Value(u8, T),
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(ctx(encode = "inherit", decode = "endian: ctx::Endian"))]
+struct InheritAsym {
+    #[declio(ctx(encode = "inherit", decode = "endian"))]
+    x: u16,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(ctx = "endian: ctx::Endian = ctx::Endian::Big, len: usize = 1")]
+struct CtxDefault {
+    #[declio(ctx = "(ctx::Len(len), endian)")]
+    x: Vec<u16>,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "e", ctx = "e: ctx::Endian = ctx::Endian::Little")]
+struct CtxDefaultEndian {
+    x: u16,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct WithCtxDefault {
+    x: CtxDefaultEndian,
+}
+
 mod little_endian {
     use super::*;
 
@@ -220,3 +382,213 @@ fn skip_if() {
         &[0x07, 0x00, 0x00, 0x00, 0x02],
     );
 }
+
+#[test]
+fn byte_len() {
+    test_bidir(
+        ByteLen {
+            size: 5,
+            inner: Struct {
+                x: 0xab,
+                y: 0xdeadbeef.into(),
+            },
+        },
+        &[0x05, 0xab, 0xde, 0xad, 0xbe, 0xef],
+    );
+    assert!(declio::from_bytes::<ByteLen>(&[0x06, 0xab, 0xde, 0xad, 0xbe, 0xef, 0x00]).is_err());
+    assert!(declio::from_bytes::<ByteLen>(&[0x04, 0xab, 0xde, 0xad, 0xbe, 0xef]).is_err());
+    assert!(declio::to_bytes(ByteLen {
+        size: 6,
+        inner: Struct {
+            x: 0xab,
+            y: 0xdeadbeef.into(),
+        },
+    })
+    .is_err());
+}
+
+#[test]
+fn byte_len_pad() {
+    test_bidir(
+        ByteLenPad {
+            inner: 0xdead.into(),
+            x: 0x12,
+        },
+        &[0xde, 0xad, 0x00, 0x00, 0x12],
+    );
+}
+
+#[test]
+fn depth_limit() {
+    let limits = declio::Limits {
+        max_depth: 3,
+        ..Default::default()
+    };
+    let tree = Tree::Node(
+        Box::new(Tree::Node(Box::new(Tree::Leaf), Box::new(Tree::Leaf))),
+        Box::new(Tree::Leaf),
+    );
+    test_bidir(tree, &[1, 1, 0, 0, 0]);
+    assert!(declio::from_bytes_with_limits::<Tree, _>(&[1, 1, 0, 0, 0], (), limits).is_ok());
+
+    let error =
+        declio::from_bytes_with_limits::<Tree, _>(&[1, 1, 1, 0, 0, 0, 0], (), limits).unwrap_err();
+    assert_eq!(error.kind(), declio::ErrorKind::DepthExceeded);
+}
+
+#[test]
+fn pod() {
+    test_bidir(
+        Pod {
+            x: 0xab,
+            y: 0xdeadbeef,
+            z: [1, -2],
+        },
+        &[0xab, 0xef, 0xbe, 0xad, 0xde, 0x01, 0x00, 0xfe, 0xff],
+    );
+    assert!(declio::from_bytes::<Pod>(&[0xab, 0xef, 0xbe, 0xad, 0xde, 0x01, 0x00, 0xfe]).is_err());
+}
+
+#[test]
+fn pod_ctx() {
+    test_bidir_ctx(
+        PodCtx(0x1234, 1.0),
+        &[0x12, 0x34, 0x3f, 0x80, 0x00, 0x00],
+        ctx::Endian::Big,
+    );
+}
+
+#[test]
+fn container_endian() {
+    test_bidir(
+        ContainerEndian {
+            x: 0x0102,
+            y: [0x03040506],
+            z: 0x0708,
+            w: 0x090a,
+            v: 0x0b0c.into(),
+            nested: ContainerCtx { y: 0x0d0e0f10 },
+        },
+        &[
+            0x02, 0x01, 0x06, 0x05, 0x04, 0x03, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x10, 0x0f,
+            0x0e, 0x0d,
+        ],
+    );
+}
+
+#[test]
+fn container_endian_ctx() {
+    test_bidir_ctx(
+        ContainerEndianCtx {
+            x: 0x0102,
+            nested: ContainerCtx { y: 0x03040506 },
+        },
+        &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+        ctx::Endian::Big,
+    );
+    test_bidir_ctx(
+        ContainerEndianBinding {
+            x: 0x0102,
+            y: vec![0x0304],
+        },
+        &[0x02, 0x01, 0x04, 0x03],
+        (ctx::Endian::Little, 1),
+    );
+    test_bidir(EndianEnum::Foo(0x0203), &[0x01, 0x00, 0x03, 0x02]);
+}
+
+#[test]
+fn endian_from() {
+    test_bidir(
+        EndianFrom {
+            magic: PcapMagic(ctx::Endian::Big),
+            version: 0x0102,
+            nested: ContainerCtx { y: 0x03040506 },
+        },
+        &[0xa1, 0xb2, 0xc3, 0xd4, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+    );
+    test_bidir(
+        EndianFrom {
+            magic: PcapMagic(ctx::Endian::Little),
+            version: 0x0102,
+            nested: ContainerCtx { y: 0x03040506 },
+        },
+        &[0xd4, 0xc3, 0xb2, 0xa1, 0x02, 0x01, 0x06, 0x05, 0x04, 0x03],
+    );
+}
+
+#[test]
+fn bounds() {
+    test_bidir(InferredBound { x: 1u8, y: 2 }, &[0x01, 0x00, 0x02]);
+    test_bidir(
+        InferredEndianBound {
+            x: 0x0102u16,
+            y: Some(BigEndian::from(0x0304u16)),
+        },
+        &[0x01, 0x02, 0x03, 0x04],
+    );
+    test_bidir_ctx(ContainerBound { x: vec![1u8, 2] }, &[0x01, 0x02], 2);
+    test_bidir(
+        FieldBound {
+            has_next: 1,
+            next: Some(Box::new(FieldBound {
+                has_next: 0,
+                next: None,
+                value: 2u8,
+            })),
+            value: 1,
+        },
+        &[0x01, 0x00, 0x02, 0x01],
+    );
+}
+
+#[test]
+fn inherit_ctx() {
+    let inherit = Inherit {
+        x: 0x0102,
+        y: [0x03040506],
+        z: 0x0708,
+        nested: ContainerCtx { y: 0x090a0b0c },
+    };
+    test_bidir_ctx(
+        inherit,
+        &[
+            0x02, 0x01, 0x06, 0x05, 0x04, 0x03, 0x07, 0x08, 0x0c, 0x0b, 0x0a, 0x09,
+        ],
+        ctx::Endian::Little,
+    );
+    test_bidir_ctx(
+        InheritEnum::Value(1, 0x0203u16),
+        &[0x01, 0x01, 0x02, 0x03],
+        ctx::Endian::Big,
+    );
+    test_bidir_ctx(InheritEnum::<u16>::Unit, &[0x00], ctx::Endian::Big);
+    test_bidir_ctx(
+        InheritAsym { x: 0x0102 },
+        &[0x02, 0x01],
+        ctx::Endian::Little,
+    );
+}
+
+#[test]
+fn ctx_default() {
+    test_bidir(CtxDefault { x: vec![0x0102] }, &[0x01, 0x02]);
+    test_bidir_ctx(
+        CtxDefault {
+            x: vec![0x0102, 0x0304],
+        },
+        &[0x02, 0x01, 0x04, 0x03],
+        (ctx::Endian::Little, 2),
+    );
+    test_bidir(
+        WithCtxDefault {
+            x: CtxDefaultEndian { x: 0x0102 },
+        },
+        &[0x02, 0x01],
+    );
+    test_bidir_ctx(
+        CtxDefaultEndian { x: 0x0102 },
+        &[0x01, 0x02],
+        ctx::Endian::Big,
+    );
+}
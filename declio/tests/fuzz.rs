@@ -0,0 +1,139 @@
+use arbitrary::{Arbitrary, Unstructured};
+use declio::ctx::{BoolRepr, Endian, Len};
+use declio::fuzz::{self, Decoded};
+use declio::{Decode, Encode};
+use proptest::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+#[declio(endian = "big")]
+struct Message {
+    len: u16,
+    #[declio(ctx = "Len(*len as usize)")]
+    payload: Vec<u8>,
+    has_extra: u8,
+    #[declio(ctx = "Endian::Big", skip_if = "*has_extra == 0")]
+    extra: Option<u32>,
+}
+
+impl Message {
+    fn is_consistent(&self) -> bool {
+        self.len as usize == self.payload.len() && (self.has_extra == 0) == self.extra.is_none()
+    }
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct Flag(#[declio(ctx = "BoolRepr::NonZero")] bool);
+
+#[derive(Debug, PartialEq, Decode)]
+#[declio(id_type = "u8")]
+enum Broken {
+    #[declio(id = "0")]
+    A,
+    #[declio(id = "1")]
+    B,
+}
+
+/// Encodes both variants with the ID of `A`.
+#[derive(Debug, PartialEq, Decode)]
+#[declio(id_type = "u8")]
+enum Asymmetric {
+    #[declio(id = "0")]
+    A,
+    #[declio(id = "1")]
+    B,
+}
+
+impl Encode for Asymmetric {
+    fn encode<W>(&self, _ctx: (), writer: &mut W) -> Result<(), declio::Error>
+    where
+        W: std::io::Write,
+    {
+        0u8.encode((), writer)
+    }
+}
+
+#[test]
+fn arbitrary() {
+    let data = [0x00, 0x02, 0xaa, 0xbb, 0x01, 0x00, 0x00, 0x00, 0x05, 0xff];
+    let mut u = Unstructured::new(&data);
+    let Decoded(message) = Decoded::<Message>::arbitrary(&mut u).unwrap();
+    assert_eq!(
+        message,
+        Message {
+            len: 2,
+            payload: vec![0xaa, 0xbb],
+            has_extra: 1,
+            extra: Some(5),
+        }
+    );
+    // Only the decoded bytes are consumed.
+    assert_eq!(u.len(), 1);
+
+    let mut u = Unstructured::new(&[0x00, 0x02, 0xaa]);
+    assert!(matches!(
+        Decoded::<Message>::arbitrary(&mut u),
+        Err(arbitrary::Error::IncorrectFormat)
+    ));
+
+    let mut u = Unstructured::new(&[0x34, 0x12]);
+    let value: u16 = fuzz::arbitrary_with_context(&mut u, Endian::Little).unwrap();
+    assert_eq!(value, 0x1234);
+}
+
+#[test]
+fn arbitrary_limits() {
+    // A length that would otherwise allocate gigabytes of unit values.
+    let mut u = Unstructured::new(&[0xff; 8]);
+    let result: arbitrary::Result<Vec<()>> = fuzz::arbitrary_with_context(&mut u, Len(usize::MAX));
+    assert!(result.is_err());
+}
+
+#[test]
+fn roundtrip() {
+    fuzz::roundtrip::<Message>(&[0x00, 0x01, 0xaa, 0x00]);
+    // Not fully consumed, or failing to decode, is ignored.
+    fuzz::roundtrip::<Message>(&[0x00, 0x01, 0xaa, 0x00, 0x00]);
+    fuzz::roundtrip::<Message>(&[0x00, 0x01]);
+    // Different encodings of the same value are allowed.
+    fuzz::roundtrip::<Flag>(&[0x02]);
+    fuzz::roundtrip_with_context::<u16, _>(&[0x12, 0x34], Endian::Big);
+}
+
+#[test]
+#[should_panic(expected = "re-decoding the encoded value gave a different value")]
+fn roundtrip_mismatch() {
+    fuzz::roundtrip::<Asymmetric>(&[0x01]);
+}
+
+#[test]
+#[should_panic(expected = "failed to encode")]
+fn roundtrip_encode_error() {
+    fuzz::roundtrip::<Broken>(&[0x01]);
+}
+
+/// Fails to encode `B`.
+impl Encode for Broken {
+    fn encode<W>(&self, _ctx: (), _writer: &mut W) -> Result<(), declio::Error>
+    where
+        W: std::io::Write,
+    {
+        match self {
+            Broken::A => Ok(()),
+            Broken::B => Err(declio::Error::new("can't encode B")),
+        }
+    }
+}
+
+proptest! {
+    #[test]
+    fn strategy(message in fuzz::strategy::<Message>()) {
+        prop_assert!(message.is_consistent());
+        let bytes = declio::to_bytes(&message).unwrap();
+        prop_assert_eq!(declio::from_bytes::<Message>(&bytes).unwrap(), message);
+    }
+
+    #[test]
+    fn strategy_with_context(values in fuzz::strategy_with_context::<Vec<u16>, _>((Len(3), Endian::Little))) {
+        prop_assert_eq!(values.len(), 3);
+    }
+}
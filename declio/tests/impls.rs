@@ -0,0 +1,403 @@
+use declio::ctx::{BoolRepr, CharRepr, Endian, Len, Shared};
+use declio::util::BigEndian;
+use declio::{Decode, Encode};
+use std::fmt::Debug;
+
+fn test_bidir_ctx<T, Ctx>(val: T, bytes: &[u8], ctx: Ctx)
+where
+    T: Encode<Ctx> + Decode<Ctx> + Debug + PartialEq,
+    Ctx: Clone,
+{
+    let output = declio::to_bytes_with_context(&val, ctx.clone()).unwrap();
+    assert_eq!(output, bytes);
+    let output: T = declio::from_bytes_with_context(bytes, ctx).unwrap();
+    assert_eq!(output, val);
+}
+
+#[test]
+fn tuple_unit_ctx() {
+    test_bidir_ctx(
+        (0xabu8, BigEndian(0xdeadbeefu32)),
+        &[0xab, 0xde, 0xad, 0xbe, 0xef],
+        (),
+    );
+}
+
+#[test]
+fn tuple_per_element_ctx() {
+    test_bidir_ctx(
+        (0xabu8, 0xdeadbeefu32, 0x1234u16),
+        &[0xab, 0xef, 0xbe, 0xad, 0xde, 0x12, 0x34],
+        ((), Endian::Little, Endian::Big),
+    );
+}
+
+#[test]
+fn tuple_shared_ctx() {
+    test_bidir_ctx(
+        (0x1234u16, 0xdeadbeefu32),
+        &[0x34, 0x12, 0xef, 0xbe, 0xad, 0xde],
+        Endian::Little,
+    );
+    test_bidir_ctx(
+        (vec![1u8, 2], vec![3u8, 4]),
+        &[0x01, 0x02, 0x03, 0x04],
+        Shared(Len(2)),
+    );
+}
+
+#[test]
+fn tuple_max_arity() {
+    test_bidir_ctx(
+        (0u8, 1u8, 2u8, 3u8, 4u8, 5u8, 6u8, 7u8, 8u8, 9u8, 10u8, 11u8),
+        &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        (),
+    );
+}
+
+#[test]
+fn vec_of_pairs() {
+    test_bidir_ctx(
+        vec![(1u8, 0x0203u16), (4u8, 0x0506u16)],
+        &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06],
+        (Len(2), Endian::Big),
+    );
+    test_bidir_ctx(
+        vec![(0x0102u16, 0x0304u16), (0x0506u16, 0x0708u16)],
+        &[0x01, 0x02, 0x04, 0x03, 0x05, 0x06, 0x08, 0x07],
+        (Len(2), (Endian::Big, Endian::Little)),
+    );
+}
+
+#[test]
+fn bool_reprs() {
+    test_bidir_ctx(true, &[0x01], BoolRepr::ZeroOne);
+    test_bidir_ctx(false, &[0x00], BoolRepr::NonZero);
+    test_bidir_ctx(true, &[0xff], BoolRepr::ZeroFF);
+    test_bidir_ctx(
+        true,
+        &[0x00, 0x00, 0x00, 0x01],
+        BoolRepr::Int {
+            size: 4,
+            endian: Endian::Big,
+            strict: true,
+        },
+    );
+    test_bidir_ctx(
+        true,
+        &[0x01, 0x00],
+        BoolRepr::Int {
+            size: 2,
+            endian: Endian::Little,
+            strict: false,
+        },
+    );
+
+    assert!(declio::from_bytes_with_context::<bool, _>(&[0x02], BoolRepr::ZeroOne).is_err());
+    assert!(declio::from_bytes_with_context::<bool, _>(&[0x02], BoolRepr::NonZero).unwrap());
+    assert!(declio::from_bytes_with_context::<bool, _>(&[0x01], BoolRepr::ZeroFF).is_err());
+    assert!(declio::from_bytes_with_context::<bool, _>(
+        &[0x00, 0x02],
+        BoolRepr::Int {
+            size: 2,
+            endian: Endian::Big,
+            strict: false,
+        }
+    )
+    .unwrap());
+}
+
+#[test]
+fn char_reprs() {
+    test_bidir_ctx('A', &[0x41], CharRepr::Utf8);
+    test_bidir_ctx('\u{20ac}', &[0xe2, 0x82, 0xac], CharRepr::Utf8);
+    test_bidir_ctx(
+        '\u{1f980}',
+        &[0x80, 0xf9, 0x01, 0x00],
+        CharRepr::Utf32(Endian::Little),
+    );
+    test_bidir_ctx('\u{e9}', &[0xe9], CharRepr::Latin1);
+
+    assert!(declio::to_bytes_with_context('\u{20ac}', CharRepr::Latin1).is_err());
+    assert!(declio::from_bytes_with_context::<char, _>(&[0xff], CharRepr::Utf8).is_err());
+    assert!(declio::from_bytes_with_context::<char, _>(
+        &[0x00, 0x00, 0xd8, 0x00],
+        CharRepr::Utf32(Endian::Big)
+    )
+    .is_err());
+}
+
+#[test]
+fn vec_to_end() {
+    use declio::ctx::ToEnd;
+
+    test_bidir_ctx(
+        vec![0x0102u16, 0x0304u16],
+        &[0x01, 0x02, 0x03, 0x04],
+        (ToEnd, Endian::Big),
+    );
+    test_bidir_ctx(Vec::<u8>::new(), &[], ToEnd);
+
+    let error =
+        declio::from_bytes_with_context::<Vec<u16>, _>(&[0x01, 0x02, 0x03], (ToEnd, Endian::Big))
+            .unwrap_err();
+    assert_eq!(error.to_string(), "truncated element at end of stream");
+}
+
+#[test]
+fn vec_until() {
+    use declio::ctx::Until;
+
+    test_bidir_ctx(vec![1u8, 2, 3], &[0x01, 0x02, 0x03, 0x00], Until(0u8));
+    test_bidir_ctx(
+        vec![(1u8, 2u8)],
+        &[0x01, 0x02, 0x00, 0x00],
+        (Until((0u8, 0u8)), ()),
+    );
+
+    assert!(declio::to_bytes_with_context(vec![1u8, 0, 3], Until(0u8)).is_err());
+    assert!(declio::from_bytes_with_context::<Vec<u8>, _>(&[0x01, 0x02], Until(0u8)).is_err());
+}
+
+#[test]
+fn utf8_until() {
+    use declio::ctx::Until;
+    use declio::util::Utf8;
+
+    test_bidir_ctx(Utf8::from("abc"), b"abc\0", Until(0u8));
+}
+
+#[test]
+fn vec_byte_len() {
+    use declio::ctx::ByteLen;
+
+    test_bidir_ctx(
+        vec![0x0102u16, 0x0304u16],
+        &[0x01, 0x02, 0x03, 0x04],
+        (ByteLen(4), Endian::Big),
+    );
+
+    assert!(declio::to_bytes_with_context(vec![1u8, 2], ByteLen(3)).is_err());
+    assert!(declio::from_bytes_with_context::<Vec<u16>, _>(
+        &[0x01, 0x02, 0x03],
+        (ByteLen(3), Endian::Big)
+    )
+    .is_err());
+}
+
+#[test]
+fn limits() {
+    use declio::ctx::ToEnd;
+    use declio::Limits;
+
+    let max_len = Limits {
+        max_len: 2,
+        ..Limits::default()
+    };
+    assert!(declio::from_bytes_with_limits::<Vec<u8>, _>(&[1, 2], Len(2), max_len).is_ok());
+    assert!(declio::from_bytes_with_limits::<Vec<u8>, _>(&[1, 2, 3], Len(3), max_len).is_err());
+    assert!(declio::from_bytes_with_limits::<Vec<u8>, _>(&[1, 2, 3], ToEnd, max_len).is_err());
+
+    let max_alloc = Limits {
+        max_alloc: 6,
+        ..Limits::default()
+    };
+    assert!(declio::from_bytes_with_limits::<Vec<u16>, _>(
+        &[0; 6],
+        (Len(3), Endian::Big),
+        max_alloc
+    )
+    .is_ok());
+    assert!(declio::from_bytes_with_limits::<Vec<Vec<u8>>, _>(
+        &[0; 8],
+        (Len(2), Len(4)),
+        max_alloc
+    )
+    .is_err());
+
+    // A huge length is rejected when the input runs out, without preallocating it.
+    assert!(declio::from_bytes_with_context::<Vec<u64>, _>(
+        &[0; 8],
+        (Len(usize::MAX / 8), Endian::Big)
+    )
+    .is_err());
+}
+
+#[test]
+fn array_of_non_copy() {
+    test_bidir_ctx(
+        [vec![1u8, 2], vec![3u8, 4]],
+        &[0x01, 0x02, 0x03, 0x04],
+        Len(2),
+    );
+}
+
+#[test]
+fn array_drops_on_error() {
+    use std::cell::Cell;
+
+    thread_local! {
+        static LIVE: Cell<isize> = const { Cell::new(0) };
+    }
+
+    struct Counted;
+
+    impl Decode for Counted {
+        fn decode<R>(_: (), reader: &mut R) -> Result<Self, declio::Error>
+        where
+            R: std::io::Read,
+        {
+            u8::decode((), reader)?;
+            LIVE.with(|live| live.set(live.get() + 1));
+            Ok(Counted)
+        }
+    }
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            LIVE.with(|live| live.set(live.get() - 1));
+        }
+    }
+
+    assert!(declio::from_bytes::<[Counted; 4]>(&[0, 0, 0]).is_err());
+    assert_eq!(LIVE.with(Cell::get), 0);
+
+    let arr = declio::from_bytes::<[Counted; 4]>(&[0, 0, 0, 0]).unwrap();
+    assert_eq!(LIVE.with(Cell::get), 4);
+    drop(arr);
+    assert_eq!(LIVE.with(Cell::get), 0);
+}
+
+#[test]
+fn primitive_sequences() {
+    test_bidir_ctx(
+        vec![0x01020304u32, 0x05060708],
+        &[0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+        (Len(2), Endian::Big),
+    );
+    test_bidir_ctx(
+        vec![0x01020304u32, 0x05060708],
+        &[0x04, 0x03, 0x02, 0x01, 0x08, 0x07, 0x06, 0x05],
+        (Len(2), Endian::Little),
+    );
+    test_bidir_ctx(
+        [1.0f32, -2.0],
+        &[0x3f, 0x80, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00],
+        Endian::Big,
+    );
+    test_bidir_ctx([0x0102i16, -2], &[0x02, 0x01, 0xfe, 0xff], Endian::Little);
+
+    // Larger than a single chunk.
+    let bytes: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+    test_bidir_ctx(bytes.clone(), &bytes, Len(bytes.len()));
+    let words: Vec<u32> = (0..3000).collect();
+    let bytes: Vec<u8> = words.iter().flat_map(|w| w.to_be_bytes()).collect();
+    test_bidir_ctx(words, &bytes, (Len(3000), Endian::Big));
+
+    assert!(declio::from_bytes_with_context::<Vec<u16>, _>(
+        &[0x01, 0x02, 0x03],
+        (Len(2), Endian::Big)
+    )
+    .is_err());
+    assert!(
+        declio::from_bytes_with_context::<[u16; 2], _>(&[0x01, 0x02, 0x03], Endian::Big).is_err()
+    );
+}
+
+#[test]
+fn word_swapped() {
+    test_bidir_ctx(
+        0x11223344u32,
+        &[0x33, 0x44, 0x11, 0x22],
+        Endian::BigWordSwapped,
+    );
+    test_bidir_ctx(
+        0x11223344u32,
+        &[0x22, 0x11, 0x44, 0x33],
+        Endian::LittleWordSwapped,
+    );
+    test_bidir_ctx(
+        0x1122334455667788u64,
+        &[0x77, 0x88, 0x55, 0x66, 0x33, 0x44, 0x11, 0x22],
+        Endian::BigWordSwapped,
+    );
+    test_bidir_ctx(0x1122u16, &[0x11, 0x22], Endian::BigWordSwapped);
+    test_bidir_ctx(0x1122u16, &[0x22, 0x11], Endian::LittleWordSwapped);
+    test_bidir_ctx(1.0f32, &[0x00, 0x00, 0x3f, 0x80], Endian::BigWordSwapped);
+    test_bidir_ctx(
+        vec![0x11223344i32, -2],
+        &[0x33, 0x44, 0x11, 0x22, 0xff, 0xfe, 0xff, 0xff],
+        (Len(2), Endian::BigWordSwapped),
+    );
+    test_bidir_ctx(
+        [0x11223344u32; 2],
+        &[0x22, 0x11, 0x44, 0x33, 0x22, 0x11, 0x44, 0x33],
+        Endian::LittleWordSwapped,
+    );
+    test_bidir_ctx(
+        true,
+        &[0x00, 0x01, 0x00, 0x00],
+        BoolRepr::Int {
+            size: 4,
+            endian: Endian::BigWordSwapped,
+            strict: true,
+        },
+    );
+
+    assert!(declio::to_bytes_with_context(
+        true,
+        BoolRepr::Int {
+            size: 3,
+            endian: Endian::LittleWordSwapped,
+            strict: true,
+        }
+    )
+    .is_err());
+}
+
+#[cfg(feature = "bulk")]
+#[test]
+fn primitive_sequences_read_in_bulk() {
+    struct CountingReader<'a>(&'a [u8], usize);
+
+    impl std::io::Read for CountingReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.1 += 1;
+            self.0.read(buf)
+        }
+    }
+
+    let bytes = [0u8; 400];
+    let mut reader = CountingReader(&bytes, 0);
+    let values: Vec<u32> = Decode::decode((Len(100), Endian::Little), &mut reader).unwrap();
+    assert_eq!(values, [0; 100]);
+    assert_eq!(reader.1, 1);
+
+    let mut reader = CountingReader(&bytes, 0);
+    let values: [i8; 400] = Decode::decode((), &mut reader).unwrap();
+    assert_eq!(values, [0; 400]);
+    assert_eq!(reader.1, 1);
+
+    struct CountingWriter(Vec<u8>, usize);
+
+    impl std::io::Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.1 += 1;
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // Byte orders other than the native one are swapped and written a chunk at a time.
+    let values = vec![0x11223344u32; 3000];
+    let mut writer = CountingWriter(Vec::new(), 0);
+    values
+        .encode((Len(3000), Endian::BigWordSwapped), &mut writer)
+        .unwrap();
+    assert_eq!(writer.0.len(), 12000);
+    assert_eq!(writer.0[..4], [0x33, 0x44, 0x11, 0x22]);
+    assert_eq!(writer.1, 3);
+}
@@ -0,0 +1,79 @@
+use declio::ctx::{Endian, Len};
+use declio::{Decode, Encode, ErrorKind};
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "big")]
+struct Record {
+    len: u8,
+    #[declio(ctx = "Len(*len as usize)")]
+    data: Vec<u8>,
+}
+
+fn records() -> Vec<Record> {
+    vec![
+        Record {
+            len: 2,
+            data: vec![0xaa, 0xbb],
+        },
+        Record {
+            len: 0,
+            data: vec![],
+        },
+        Record {
+            len: 1,
+            data: vec![0xcc],
+        },
+    ]
+}
+
+const BYTES: [u8; 6] = [0x02, 0xaa, 0xbb, 0x00, 0x01, 0xcc];
+
+#[test]
+fn decode_iter() {
+    let decoded: Result<Vec<Record>, _> = declio::decode_iter(&BYTES[..], ()).collect();
+    assert_eq!(decoded.unwrap(), records());
+
+    assert_eq!(declio::decode_iter::<Record, _, _>(&[][..], ()).count(), 0);
+}
+
+#[test]
+fn decode_iter_truncated() {
+    let bytes = [0x01, 0xaa, 0x02, 0xbb];
+    let mut iter = declio::decode_iter::<Record, _, _>(&bytes[..], ());
+    assert_eq!(
+        iter.next().unwrap().unwrap(),
+        Record {
+            len: 1,
+            data: vec![0xaa],
+        }
+    );
+    let error = iter.next().unwrap().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    assert!(iter.next().is_none());
+}
+
+#[test]
+fn decode_iter_error() {
+    // The iterator ends after any error, not only at the end of the stream.
+    let bytes = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05];
+    let mut iter = declio::decode_iter::<Vec<u16>, _, _>(&bytes[..], (Len(2), Endian::Big));
+    assert_eq!(iter.next().unwrap().unwrap(), [0, 0]);
+    declio::Limits {
+        max_len: 1,
+        ..declio::Limits::default()
+    }
+    .scope(|| assert!(iter.next().unwrap().is_err()));
+    assert!(iter.next().is_none());
+    assert!(!iter.into_inner().is_empty());
+}
+
+#[test]
+fn encode_iter() {
+    let mut bytes = Vec::new();
+    declio::encode_iter(&mut bytes, records(), ()).unwrap();
+    assert_eq!(bytes, BYTES);
+
+    let mut bytes = Vec::new();
+    declio::encode_iter(&mut bytes, [1u16, 2], Endian::Little).unwrap();
+    assert_eq!(bytes, [0x01, 0x00, 0x02, 0x00]);
+}
@@ -0,0 +1,106 @@
+use declio::layout::{ContainerKind, FieldLayout};
+use declio::util::BigEndian;
+use declio::{ctx, Decode, Encode, Layout};
+
+#[derive(Encode, Decode, Layout)]
+#[declio(ctx = "endian: ctx::Endian", endian = "endian")]
+struct Header {
+    version: u16,
+    flags: [u8; 2],
+    #[declio(ctx = "ctx::Len(*version as usize)")]
+    data: Vec<u8>,
+    #[declio(skip_if = "*version == 0")]
+    extra: Option<BigEndian<u32>>,
+}
+
+#[derive(Encode, Decode, Layout)]
+#[declio(id_type = "u8")]
+enum Message {
+    #[declio(id = "0")]
+    Ping,
+    #[declio(id = "1")]
+    Data(
+        #[declio(ctx = "ctx::Endian::Little")] u32,
+        #[declio(byte_len = "4")] BigEndian<u32>,
+    ),
+}
+
+#[derive(Encode, Decode, Layout)]
+#[declio(pod = "ctx::Endian::Little")]
+struct Pod {
+    x: u32,
+    y: [i16; 2],
+}
+
+#[test]
+fn struct_layout() {
+    let layout = Header::layout();
+    assert_eq!(layout.name, "Header");
+    assert_eq!(layout.kind, ContainerKind::Struct);
+    assert_eq!(layout.encode_ctx, Some("endian: ctx::Endian"));
+    assert_eq!(layout.size(), None);
+    assert_eq!(
+        layout.variants[0].fields[0],
+        FieldLayout {
+            name: "version",
+            ty: "u16",
+            encode_ctx: Some("endian"),
+            decode_ctx: Some("endian"),
+            with: None,
+            skip_if: None,
+            byte_len: None,
+            size: Some(2),
+        }
+    );
+    assert_eq!(layout.variants[0].fields[3].ty, "Option<BigEndian<u32>>");
+    assert_eq!(layout.variants[0].fields[3].skip_if, Some("*version == 0"));
+
+    assert_eq!(
+        layout.to_markdown(),
+        "## `Header`\n\
+         \n\
+         - Context: `endian: ctx::Endian`\n\
+         \n\
+         | Field | Type | Offset | Size | Context | Notes |\n\
+         | --- | --- | --- | --- | --- | --- |\n\
+         | `version` | `u16` | 0 | 2 | `endian` |  |\n\
+         | `flags` | `[u8; 2]` | 2 | 2 | `endian` |  |\n\
+         | `data` | `Vec<u8>` | 4 |  | `ctx::Len(*version as usize)` |  |\n\
+         | `extra` | `Option<BigEndian<u32>>` |  |  |  | skipped if `*version == 0` |\n"
+    );
+}
+
+#[test]
+fn enum_layout() {
+    let layout = Message::layout();
+    assert_eq!(layout.kind, ContainerKind::Enum);
+    assert_eq!(layout.id_type, Some("u8"));
+    assert_eq!(layout.id_size, Some(1));
+    assert_eq!(layout.variants[0].size(), Some(0));
+    assert_eq!(layout.variants[1].size(), Some(8));
+    assert_eq!(layout.size(), None);
+
+    assert_eq!(
+        layout.to_markdown(),
+        "## `Message`\n\
+         \n\
+         - ID type: `u8`\n\
+         \n\
+         ### `Ping` (ID `0`)\n\
+         \n\
+         No fields.\n\
+         \n\
+         ### `Data` (ID `1`)\n\
+         \n\
+         | Field | Type | Offset | Size | Context | Notes |\n\
+         | --- | --- | --- | --- | --- | --- |\n\
+         | `0` | `u32` | 0 | 4 | `ctx::Endian::Little` |  |\n\
+         | `1` | `BigEndian<u32>` | 4 | 4 |  | `4` bytes |\n"
+    );
+}
+
+#[test]
+fn pod_layout() {
+    let layout = Pod::layout();
+    assert_eq!(layout.size(), Some(8));
+}
@@ -0,0 +1,84 @@
+use declio::ctx::{Len, Until};
+use declio::{Decode, Encode, ErrorKind, Partial};
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "big")]
+struct Message {
+    len: u16,
+    #[declio(ctx = "Len(*len as usize)")]
+    payload: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct Framed {
+    size: u8,
+    #[declio(byte_len = "*size as usize")]
+    inner: Message,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(id_type = "u8")]
+enum Kind {
+    #[declio(id = "0")]
+    A,
+}
+
+#[test]
+fn chunks() {
+    let bytes = [0x00, 0x03, 0xaa, 0xbb, 0xcc, 0xff];
+    let needed: Vec<Option<usize>> = (0..5)
+        .map(
+            |len| match declio::decode_partial::<Message>(&bytes[..len]).unwrap() {
+                Partial::Incomplete { needed } => needed,
+                complete => panic!("unexpected {:?}", complete),
+            },
+        )
+        .collect();
+    assert_eq!(needed, [Some(2), Some(1), Some(3), Some(2), Some(1)]);
+
+    let message = Message {
+        len: 3,
+        payload: vec![0xaa, 0xbb, 0xcc],
+    };
+    for len in 5..=6 {
+        assert_eq!(
+            declio::decode_partial::<Message>(&bytes[..len]).unwrap(),
+            Partial::Complete {
+                value: Message {
+                    len: 3,
+                    payload: vec![0xaa, 0xbb, 0xcc],
+                },
+                len: 5,
+            }
+        );
+    }
+    assert_eq!(
+        declio::decode_partial::<Message>(&bytes)
+            .unwrap()
+            .complete(),
+        Some(message)
+    );
+}
+
+#[test]
+fn unknown_needed() {
+    let result = declio::decode_partial_with_context::<Vec<u8>, _>(&[1, 2], Until(0)).unwrap();
+    assert_eq!(result, Partial::Incomplete { needed: None });
+    assert!(!result.is_complete());
+}
+
+#[test]
+fn errors() {
+    // Invalid input is an error, even if it is incomplete.
+    let error = declio::decode_partial::<Kind>(&[0x01]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Other);
+
+    // Running out of bytes within a `byte_len` is an error, but running out of the buffer is not.
+    let error = declio::decode_partial::<Framed>(&[0x03, 0x00, 0x02, 0xaa, 0xbb]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    // The number of missing bytes is not known through the reader that limits the field.
+    assert_eq!(
+        declio::decode_partial::<Framed>(&[0x04, 0x00, 0x02, 0xaa]).unwrap(),
+        Partial::Incomplete { needed: None }
+    );
+}
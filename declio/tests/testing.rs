@@ -0,0 +1,103 @@
+use declio::ctx::{Endian, Len};
+use declio::testing::{assert_decode_error, assert_golden_dir, assert_roundtrip};
+use declio::{Decode, Encode, ErrorKind, Limits};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "big")]
+struct Message {
+    kind: u8,
+    len: u16,
+    #[declio(ctx = "Len(*len as usize)")]
+    payload: Vec<u8>,
+}
+
+/// Creates an empty directory for golden files.
+fn golden_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn roundtrip() {
+    let message = Message {
+        kind: 1,
+        len: 2,
+        payload: vec![0xaa, 0xbb],
+    };
+    assert_roundtrip(&message, (), &[0x01, 0x00, 0x02, 0xaa, 0xbb]);
+    assert_roundtrip(&0x1234u16, Endian::Little, &[0x34, 0x12]);
+}
+
+#[test]
+#[should_panic(expected = "first difference at offset 0x1")]
+fn roundtrip_wrong_bytes() {
+    assert_roundtrip(&0x1234u16, Endian::Big, &[0x12, 0x35]);
+}
+
+#[test]
+fn decode_error() {
+    let error =
+        assert_decode_error::<Message, _>(&[0x01, 0x00, 0x02, 0xaa], (), ErrorKind::UnexpectedEof);
+    assert_eq!(error.to_string(), "error decoding field payload");
+
+    Limits {
+        max_len: 1,
+        ..Limits::default()
+    }
+    .scope(|| {
+        assert_decode_error::<Message, _>(
+            &[0x01, 0x00, 0x02, 0xaa, 0xbb],
+            (),
+            ErrorKind::LimitExceeded,
+        )
+    });
+}
+
+#[test]
+#[should_panic(expected = "expected an error of kind UnexpectedEof, but decoded")]
+fn decode_error_success() {
+    assert_decode_error::<u8, _>(&[0x01], (), ErrorKind::UnexpectedEof);
+}
+
+#[test]
+#[should_panic(expected = "expected an error of kind LimitExceeded, got UnexpectedEof")]
+fn decode_error_wrong_kind() {
+    assert_decode_error::<u16, _>(&[0x01], Endian::Big, ErrorKind::LimitExceeded);
+}
+
+#[test]
+fn golden() {
+    let dir = golden_dir("golden");
+    fs::write(dir.join("empty.bin"), [0x00, 0x00, 0x00]).unwrap();
+    fs::write(dir.join("payload.bin"), [0x02, 0x00, 0x03, 1, 2, 3]).unwrap();
+    fs::create_dir(dir.join("ignored")).unwrap();
+    assert_eq!(assert_golden_dir::<Message, _>(&dir, ()), 2);
+}
+
+#[test]
+fn golden_failures() {
+    let dir = golden_dir("golden_failures");
+    fs::write(dir.join("a.bin"), [0x00, 0x00, 0x00]).unwrap();
+    fs::write(dir.join("b.bin"), [0x00, 0x00, 0x01]).unwrap();
+    fs::write(dir.join("c.bin"), [0x00, 0x00, 0x00, 0xff]).unwrap();
+
+    let message = std::panic::catch_unwind(|| assert_golden_dir::<Message, _>(&dir, ()))
+        .unwrap_err()
+        .downcast::<String>()
+        .unwrap();
+    assert!(!message.contains("a.bin"));
+    assert!(message.contains(
+        "b.bin: failed to decode: error decoding field payload: failed to fill whole buffer\n"
+    ));
+    assert!(message.contains("c.bin: failed to decode: input was not fully consumed"));
+}
+
+#[test]
+#[should_panic(expected = "no samples found")]
+fn golden_empty() {
+    assert_golden_dir::<Message, _>(golden_dir("golden_empty"), ());
+}
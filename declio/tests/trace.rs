@@ -0,0 +1,219 @@
+use declio::ctx::{Len, ToEnd};
+use declio::trace::{self, SpanKind};
+use declio::{Decode, Encode};
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "big")]
+struct Packet {
+    kind: u8,
+    count: u8,
+    #[declio(ctx = "Len(*count as usize)")]
+    entries: Vec<Entry>,
+    body: Body,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(endian = "little")]
+struct Entry {
+    key: u8,
+    value: u16,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(id_type = "u8")]
+enum Body {
+    #[declio(id = "0")]
+    Empty,
+    #[declio(id = "1")]
+    Text(#[declio(ctx = "Len(4)")] Vec<u8>),
+}
+
+#[derive(Encode, Decode)]
+struct NoDebug(u8);
+
+#[derive(Encode, Decode)]
+struct Outer {
+    inner: NoDebug,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+#[declio(pod = "declio::ctx::Endian::Big")]
+struct Point {
+    x: u16,
+    y: u16,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct Shape {
+    kind: u8,
+    origin: Point,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct Item {
+    a: u8,
+    b: u8,
+}
+
+#[derive(Debug, PartialEq, Encode, Decode)]
+struct Items {
+    tag: u8,
+    #[declio(ctx = "ToEnd")]
+    items: Vec<Item>,
+}
+
+#[test]
+fn spans() {
+    let bytes = [
+        0x07, 0x02, 0x01, 0x34, 0x12, 0x02, 0x78, 0x56, 0x01, b'a', b'b', b'c', b'd',
+    ];
+    let (result, trace) = trace::from_bytes::<Packet>(&bytes);
+    assert!(result.is_ok());
+    assert!(trace.failed().is_none());
+
+    let root = &trace.spans()[0];
+    assert_eq!(root.kind, SpanKind::Type);
+    assert_eq!(root.name, "Packet");
+    assert_eq!((root.start, root.end), (0, 13));
+
+    let paths: Vec<(&str, u64, u64)> = trace
+        .leaves()
+        .iter()
+        .map(|span| (span.path.as_str(), span.start, span.end))
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            ("kind", 0, 1),
+            ("count", 1, 2),
+            ("entries[0].key", 2, 3),
+            ("entries[0].value", 3, 5),
+            ("entries[1].key", 5, 6),
+            ("entries[1].value", 6, 8),
+            ("body.id", 8, 9),
+            ("body.0", 9, 13),
+        ]
+    );
+
+    let body = &root.children[3];
+    assert_eq!(body.value.as_deref(), Some("Text([97, 98, 99, 100])"));
+    assert_eq!(body.children[0].variant, Some("Text"));
+    assert_eq!(body.children[0].children[0].kind, SpanKind::Id);
+
+    assert_eq!(
+        trace.hex_dump(&bytes),
+        "00000000  07                                               kind = 7\n\
+         00000001  02                                               count = 2\n\
+         00000002  01                                               entries[0].key = 1\n\
+         00000003  34 12                                            entries[0].value = 4660\n\
+         00000005  02                                               entries[1].key = 2\n\
+         00000006  78 56                                            entries[1].value = 22136\n\
+         00000008  01                                               body.id = 1\n\
+         00000009  61 62 63 64                                      body.0 = [97, 98, 99, 100]\n"
+    );
+}
+
+#[test]
+fn to_end_spans() {
+    // Elements decoded until the end of the stream start with a byte that was read ahead.
+    let bytes = [9, 1, 2, 3, 4];
+    let (result, trace) = trace::from_bytes::<Items>(&bytes);
+    assert!(result.is_ok());
+    let paths: Vec<(&str, u64, u64)> = trace
+        .leaves()
+        .iter()
+        .map(|span| (span.path.as_str(), span.start, span.end))
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            ("tag", 0, 1),
+            ("items[0].a", 1, 2),
+            ("items[0].b", 2, 3),
+            ("items[1].a", 3, 4),
+            ("items[1].b", 4, 5),
+        ]
+    );
+    assert!(!trace.hex_dump(&bytes).contains("not traced"));
+}
+
+#[test]
+fn failure() {
+    let bytes = [0x07, 0x02, 0x01, 0x34, 0x12, 0x02, 0x78];
+    let (result, trace) = trace::from_bytes::<Packet>(&bytes);
+    assert!(result.is_err());
+
+    let failed = trace.failed().unwrap();
+    assert_eq!(failed.path, "entries[1].value");
+    assert_eq!((failed.start, failed.end), (6, 7));
+    assert!(!trace.spans()[0].complete);
+    assert!(trace.spans()[0].children[1].complete);
+
+    assert_eq!(
+        trace.hex_dump(&bytes),
+        "00000000  07                                               kind = 7\n\
+         00000001  02                                               count = 2\n\
+         00000002  01                                               entries[0].key = 1\n\
+         00000003  34 12                                            entries[0].value = 4660\n\
+         00000005  02                                               entries[1].key = 2\n\
+         00000006  78                                               entries[1].value: error: failed to fill whole buffer\n"
+    );
+
+    // Unknown enum IDs fail the enum itself, after its ID was decoded.
+    let bytes = [0x07, 0x00, 0x05];
+    let (_, trace) = trace::from_bytes::<Packet>(&bytes);
+    let failed = trace.failed().unwrap();
+    assert_eq!(failed.kind, SpanKind::Type);
+    assert_eq!(failed.path, "body");
+    assert_eq!(failed.error.as_deref(), Some("unknown id value"));
+    assert!(trace
+        .hex_dump(&bytes)
+        .ends_with("body.id = 5\nerror in body: unknown id value\n"));
+}
+
+#[test]
+fn trailing_bytes() {
+    let bytes = [0x07, 0x00, 0x00, 0xff, 0xff];
+    let (result, trace) = trace::from_bytes::<Packet>(&bytes);
+    assert!(result.is_err());
+    assert!(trace.failed().is_none());
+    assert!(trace
+        .hex_dump(&bytes)
+        .ends_with("00000003  ff ff                                            (not decoded)\n"));
+}
+
+#[test]
+fn pod_spans() {
+    let (result, trace) = trace::from_bytes::<Shape>(&[0x01, 0x00, 0x02, 0x00, 0x03]);
+    assert!(result.is_ok());
+    let origin = &trace.spans()[0].children[1];
+    assert_eq!((origin.start, origin.end), (1, 5));
+    assert_eq!(origin.children[0].kind, SpanKind::Type);
+    assert_eq!(origin.children[0].name, "Point");
+    assert_eq!(origin.value.as_deref(), Some("Point { x: 2, y: 3 }"));
+}
+
+#[test]
+fn without_debug() {
+    let (result, trace) = trace::from_bytes::<Outer>(&[0x01]);
+    assert!(result.is_ok());
+    let leaves = trace.leaves();
+    assert_eq!(leaves[0].path, "inner.0");
+    assert_eq!(leaves[0].value.as_deref(), Some("1"));
+    assert_eq!(trace.spans()[0].children[0].value, None);
+}
+
+#[test]
+fn not_recorded() {
+    // Decoding outside of a trace works as usual.
+    let bytes = [0x07, 0x00, 0x00];
+    assert_eq!(
+        declio::from_bytes::<Packet>(&bytes).unwrap(),
+        Packet {
+            kind: 7,
+            count: 0,
+            entries: Vec::new(),
+            body: Body::Empty,
+        }
+    );
+}
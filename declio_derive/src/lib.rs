@@ -14,6 +14,16 @@ pub fn derive_encode(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         .into()
 }
 
+#[proc_macro_derive(Layout, attributes(declio))]
+pub fn derive_layout(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    ContainerReceiver::from_derive_input(&input)
+        .and_then(|receiver| receiver.validate())
+        .map(|data| data.layout_impl().into_token_stream())
+        .unwrap_or_else(|error| error.write_errors())
+        .into()
+}
+
 #[proc_macro_derive(Decode, attributes(declio))]
 pub fn derive_decode(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
@@ -45,23 +55,50 @@ struct ContainerReceiver {
 
     #[darling(default)]
     id_ctx: Asym<syn::LitStr>,
+
+    #[darling(default)]
+    pod: Option<syn::LitStr>,
+
+    #[darling(default)]
+    endian: Option<syn::LitStr>,
+
+    #[darling(default)]
+    endian_from: Option<syn::LitStr>,
+
+    #[darling(default)]
+    bound: Asym<syn::LitStr>,
 }
 
 struct ContainerData {
     ident: syn::Ident,
     generics: syn::Generics,
+    encode_bound: Option<Vec<syn::WherePredicate>>,
+    decode_bound: Option<Vec<syn::WherePredicate>>,
     crate_path: syn::Path,
     encode_ctx_pat: TokenStream,
     decode_ctx_pat: TokenStream,
     encode_ctx_type: TokenStream,
     decode_ctx_type: TokenStream,
+    encode_ctx_default: Option<TokenStream>,
+    decode_ctx_default: Option<TokenStream>,
+    inherit_ctx: InheritCtx,
     id_encode_ctx: TokenStream,
     id_decode_ctx: TokenStream,
-    id_encoder: Option<TokenStream>,
-    id_decoder: Option<TokenStream>,
+    id_type: Option<TokenStream>,
+    id_size: Option<TokenStream>,
     id_check_expr: Option<TokenStream>,
     id_decode_expr: Option<TokenStream>,
+    pod_endian: Option<TokenStream>,
     variants: Vec<VariantData>,
+    text: ContainerText,
+}
+
+/// The attributes of a container as source text, for the `Layout` impl.
+struct ContainerText {
+    is_enum: bool,
+    encode_ctx: Option<String>,
+    decode_ctx: Option<String>,
+    id_type: Option<String>,
 }
 
 impl ContainerReceiver {
@@ -76,9 +113,10 @@ impl ContainerReceiver {
             .unwrap_or_else(|| parse_quote!(declio));
 
         let mut parse_ctx = |arg: Option<&syn::LitStr>| match arg {
-            None => (quote!(_), quote!(())),
+            None => (quote!(_), quote!(()), None),
+            Some(lit) if lit.value() == "inherit" => (quote!(ctx), quote!(Ctx), None),
             Some(lit) => {
-                let parts: Punctuated<syn::FnArg, Token![,]> =
+                let bindings: Punctuated<CtxBinding, Token![,]> =
                     match lit.parse_with(Punctuated::parse_terminated) {
                         Ok(punct) => punct,
                         Err(error) => {
@@ -87,38 +125,117 @@ impl ContainerReceiver {
                         }
                     };
 
-                let parts: Vec<_> = parts
-                    .into_iter()
-                    .flat_map(|fn_arg| match fn_arg {
-                        syn::FnArg::Typed(pat_type) => Some((pat_type.pat, pat_type.ty)),
-                        _ => {
-                            errors.push(
-                                Error::custom("expected name and type, like `foo: i64`")
-                                    .with_span(&fn_arg),
-                            );
-                            None
-                        }
-                    })
+                let pats = bindings.iter().map(|binding| &binding.pat);
+                let types = bindings.iter().map(|binding| &binding.ty);
+                let defaults: Option<Vec<_>> = bindings
+                    .iter()
+                    .map(|binding| binding.default.as_ref())
                     .collect();
-
-                let pats = parts.iter().map(|(pat, _)| pat);
-                let types = parts.iter().map(|(_, ty)| ty);
+                if defaults.is_none() && bindings.iter().any(|binding| binding.default.is_some()) {
+                    errors.push(
+                        Error::custom("either all or none of the context fields need defaults")
+                            .with_span(lit),
+                    );
+                }
 
                 // Special case: single context variable gets to be not-a-tuple.
-                if parts.len() == 1 {
-                    (quote!( #( #pats )* ), quote!( #( #types )* ))
+                if bindings.len() == 1 {
+                    (
+                        quote!( #( #pats )* ),
+                        quote!( #( #types )* ),
+                        defaults.map(|defaults| quote!( #( #defaults )* )),
+                    )
                 } else {
-                    (quote!( ( #( #pats , )* ) ), quote!( ( #( #types , )* ) ))
+                    (
+                        quote!( ( #( #pats , )* ) ),
+                        quote!( ( #( #types , )* ) ),
+                        defaults.map(|defaults| quote!( ( #( #defaults , )* ) )),
+                    )
                 }
             }
         };
 
-        let (encode_ctx_pat, encode_ctx_type) = parse_ctx(self.ctx.encode());
-        let (decode_ctx_pat, decode_ctx_type) = parse_ctx(self.ctx.decode());
+        // `endian = "ctx"` is shorthand for `ctx = "endian: Endian"`.
+        let endian_from_ctx = self.endian.as_ref().is_some_and(|lit| lit.value() == "ctx");
+        let endian_ctx = (quote!(endian), quote!(#crate_path::ctx::Endian), None);
+
+        let (encode_ctx_pat, encode_ctx_type, encode_ctx_default) = if endian_from_ctx {
+            endian_ctx.clone()
+        } else {
+            parse_ctx(self.ctx.encode())
+        };
+        let (decode_ctx_pat, decode_ctx_type, decode_ctx_default) = if endian_from_ctx {
+            endian_ctx
+        } else {
+            parse_ctx(self.ctx.decode())
+        };
+
+        let inherit_ctx = InheritCtx {
+            encode: self
+                .ctx
+                .encode()
+                .is_some_and(|lit| lit.value() == "inherit"),
+            decode: self
+                .ctx
+                .decode()
+                .is_some_and(|lit| lit.value() == "inherit"),
+        };
+        if inherit_ctx.any() {
+            if self
+                .generics
+                .type_params()
+                .any(|param| param.ident == "Ctx")
+            {
+                errors.push(Error::custom(
+                    "`ctx = \"inherit\"` adds a type parameter named `Ctx`, which is already used",
+                ));
+            }
+            if self.pod.is_some() {
+                errors.push(Error::custom(
+                    "`ctx = \"inherit\"` and `pod` are incompatible with each other",
+                ));
+            }
+        }
+
+        if endian_from_ctx && self.ctx.is_some() {
+            errors.push(Error::custom(
+                "`endian = \"ctx\"` conflicts with `ctx`; use the name of a context binding instead",
+            ));
+        }
+
+        let endian = match (&self.endian, &self.endian_from) {
+            (Some(_), None) if endian_from_ctx => {
+                Some(DefaultEndian::new(quote!(endian), "endian".into()))
+            }
+            (Some(lit), None) => match parse_endian(lit, &crate_path) {
+                Ok(expr) => Some(DefaultEndian::new(expr, endian_text(lit))),
+                Err(error) => {
+                    errors.push(error);
+                    None
+                }
+            },
+            (None, Some(lit)) => match lit.parse::<TokenStream>() {
+                Ok(expr) => Some(DefaultEndian {
+                    expr: quote!(#crate_path::ctx::Endian::from(#expr)),
+                    text: format!("Endian::from({})", lit.value()),
+                    source: expr,
+                }),
+                Err(error) => {
+                    errors.push(from_syn_error(error));
+                    None
+                }
+            },
+            (Some(..), Some(..)) => {
+                errors.push(Error::custom(
+                    "`endian` and `endian_from` are incompatible with each other",
+                ));
+                None
+            }
+            (None, None) => None,
+        };
 
-        let (id_encoder, id_decoder, id_decode_expr) = match (&self.id_expr.decode(), &self.id_type)
-        {
-            (None, None) => (None, None, Some(quote!(()))),
+        let (id_type, id_decode_expr) = match (&self.id_expr.decode(), &self.id_type) {
+            (None, None) => (None, Some(quote!(()))),
             (Some(lit), None) => {
                 let expr = match lit.parse() {
                     Ok(expr) => expr,
@@ -127,7 +244,7 @@ impl ContainerReceiver {
                         quote!(unreachable!("compile error"))
                     }
                 };
-                (None, None, Some(expr))
+                (None, Some(expr))
             }
             (None, Some(lit)) => {
                 let ty = match lit.parse() {
@@ -137,17 +254,13 @@ impl ContainerReceiver {
                         quote!(())
                     }
                 };
-                (
-                    Some(quote!( <#ty as #crate_path::Encode<_>>::encode )),
-                    Some(quote!( <#ty as #crate_path::Decode<_>>::decode )),
-                    None,
-                )
+                (Some(ty), None)
             }
             (Some(..), Some(..)) => {
                 errors.push(Error::custom(
                     "`id_expr(decode = \"...\")` and `id_type` are incompatible with each other",
                 ));
-                (None, None, None)
+                (None, None)
             }
         };
         let id_check_expr = match &self.id_expr.encode() {
@@ -164,6 +277,9 @@ impl ContainerReceiver {
             None => None,
         };
 
+        let encode_bound = parse_bound(self.bound.encode(), &mut errors);
+        let decode_bound = parse_bound(self.bound.decode(), &mut errors);
+
         let mut parse_id_ctx = |arg: Option<&syn::LitStr>| match arg {
             None => quote!(()),
             Some(lit) => match lit.parse() {
@@ -175,8 +291,42 @@ impl ContainerReceiver {
             },
         };
 
-        let id_encode_ctx = parse_id_ctx(self.id_ctx.encode());
-        let id_decode_ctx = parse_id_ctx(self.id_ctx.decode());
+        let mut id_encode_ctx = parse_id_ctx(self.id_ctx.encode());
+        let mut id_decode_ctx = parse_id_ctx(self.id_ctx.decode());
+
+        // Primitive ID types also use the container's endianness by default, unless it comes
+        // from `endian_from`, which may depend on fields that are decoded after the ID.
+        if let (Some(endian), Some(lit), None) = (&endian, &self.id_type, &self.endian_from) {
+            let endian = &endian.expr;
+            if lit.parse().is_ok_and(|ty| is_primitive(&ty)) {
+                if self.id_ctx.encode().is_none() {
+                    id_encode_ctx = endian.clone();
+                }
+                if self.id_ctx.decode().is_none() {
+                    id_decode_ctx = endian.clone();
+                }
+            }
+        }
+
+        let id_size = id_type
+            .as_ref()
+            .and_then(|ty| syn::parse2(ty.clone()).ok())
+            .filter(is_primitive)
+            .map(|ty: syn::Type| quote!(#crate_path::export::mem::size_of::<#ty>()));
+
+        let ctx_text = |arg: Option<&syn::LitStr>| {
+            if endian_from_ctx {
+                Some("endian: Endian".to_owned())
+            } else {
+                arg.map(syn::LitStr::value)
+            }
+        };
+        let text = ContainerText {
+            is_enum: self.data.is_enum(),
+            encode_ctx: ctx_text(self.ctx.encode()),
+            decode_ctx: ctx_text(self.ctx.decode()),
+            id_type: self.id_type.as_ref().map(syn::LitStr::value),
+        };
 
         if self.data.is_struct() && self.id_expr.is_some() {
             errors.push(Error::unknown_field("id_expr"));
@@ -190,42 +340,94 @@ impl ContainerReceiver {
             ));
         }
 
+        let pod_endian = match &self.pod {
+            Some(lit) => {
+                match &self.data {
+                    ast::Data::Struct(fields) => {
+                        for field in fields.iter() {
+                            if field.has_codec_attrs() {
+                                errors.push(
+                                    Error::custom("fields of `pod` structs cannot have attributes")
+                                        .with_span(&field.ty),
+                                );
+                            }
+                        }
+                    }
+                    ast::Data::Enum(..) => {
+                        errors.push(Error::custom("`pod` is not supported for enums"))
+                    }
+                }
+                // The size of the struct is used as an array length, which can't depend on generic
+                // parameters.
+                if self
+                    .generics
+                    .params
+                    .iter()
+                    .any(|param| !matches!(param, syn::GenericParam::Lifetime(_)))
+                {
+                    errors.push(
+                        Error::custom("`pod` is not supported for generic types")
+                            .with_span(&self.generics),
+                    );
+                }
+                match lit.parse() {
+                    Ok(expr) => Some(expr),
+                    Err(error) => {
+                        errors.push(from_syn_error(error));
+                        Some(quote!(unreachable!("compile error")))
+                    }
+                }
+            }
+            None => None,
+        };
+
         let variants = match &self.data {
             ast::Data::Enum(variants) => variants
                 .iter()
-                .flat_map(|variant| match variant.validate(&crate_path) {
-                    Ok(data) => Some(data),
-                    Err(error) => {
-                        errors.push(error);
-                        None
+                .flat_map(|variant| {
+                    match variant.validate(&crate_path, endian.as_ref(), inherit_ctx) {
+                        Ok(data) => Some(data),
+                        Err(error) => {
+                            errors.push(error);
+                            None
+                        }
                     }
                 })
                 .collect(),
-            ast::Data::Struct(fields) => match VariantData::from_struct(fields, &crate_path) {
-                Ok(data) => vec![data],
-                Err(error) => {
-                    errors.push(error);
-                    vec![]
+            ast::Data::Struct(fields) => {
+                match VariantData::from_struct(fields, &crate_path, endian.as_ref(), inherit_ctx) {
+                    Ok(data) => vec![data],
+                    Err(error) => {
+                        errors.push(error);
+                        vec![]
+                    }
                 }
-            },
+            }
         };
 
         if errors.is_empty() {
             Ok(ContainerData {
                 ident,
                 generics,
+                encode_bound,
+                decode_bound,
                 crate_path,
                 encode_ctx_pat,
                 decode_ctx_pat,
                 encode_ctx_type,
                 decode_ctx_type,
+                encode_ctx_default,
+                decode_ctx_default,
+                inherit_ctx,
                 id_encode_ctx,
                 id_decode_ctx,
-                id_encoder,
-                id_decoder,
+                id_type,
+                id_size,
                 id_decode_expr,
                 id_check_expr,
+                pod_endian,
                 variants,
+                text,
             })
         } else {
             Err(Error::multiple(errors))
@@ -242,17 +444,35 @@ impl ContainerData {
             encode_ctx_type,
             ..
         } = self;
-        let (impl_generics, ident_generics, where_clause) = self.generics.split_for_impl();
+        let generics = self.generics_with_bounds(true);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let (_, ident_generics, _) = self.generics.split_for_impl();
         let writer_binding = quote!(__declio_writer);
 
-        let variant_arm = self.variants.iter().map(|variant| {
-            variant.encode_arm(
-                self.id_encoder.as_ref(),
-                &self.id_encode_ctx,
-                self.id_check_expr.as_ref(),
-                &self.crate_path,
-                &writer_binding,
-            )
+        let body = match &self.pod_endian {
+            Some(endian) => self.variants[0].pod_encode_body(endian, crate_path, &writer_binding),
+            None => self.encode_body(&writer_binding),
+        };
+
+        // With default context values, the type can also be encoded without context.
+        let default_impl = self.encode_ctx_default.as_ref().map(|default| {
+            quote! {
+                impl #impl_generics #crate_path::Encode<()> for #ident #ident_generics
+                    #where_clause
+                {
+                    fn encode<W>(&self, _: (), #writer_binding: &mut W)
+                        -> Result<(), #crate_path::Error>
+                    where
+                        W: #crate_path::export::io::Write,
+                    {
+                        <Self as #crate_path::Encode<#encode_ctx_type>>::encode(
+                            self,
+                            #default,
+                            #writer_binding,
+                        )
+                    }
+                }
+            }
         });
 
         quote! {
@@ -265,11 +485,102 @@ impl ContainerData {
                 where
                     W: #crate_path::export::io::Write,
                 {
-                    match self {
-                        #( #variant_arm, )*
-                    }
+                    #body
                 }
             }
+
+            #default_impl
+        }
+    }
+
+    /// Returns the generics of the `Encode` or `Decode` impl, which are the container's generics
+    /// with the bounds required by the trait added to the where clause, and the `Ctx` type
+    /// parameter if the context is inherited.
+    ///
+    /// Unless they are given by a `bound` attribute, the bounds are inferred from the types of the
+    /// fields that mention a type parameter, as long as the type of the field's context is known.
+    fn generics_with_bounds(&self, is_encode: bool) -> syn::Generics {
+        let crate_path = &self.crate_path;
+        let (trait_path, container_bound, inherit_ctx) = if is_encode {
+            (
+                quote!(#crate_path::Encode),
+                &self.encode_bound,
+                self.inherit_ctx.encode,
+            )
+        } else {
+            (
+                quote!(#crate_path::Decode),
+                &self.decode_bound,
+                self.inherit_ctx.decode,
+            )
+        };
+
+        let mut generics = self.generics.clone();
+        if inherit_ctx {
+            generics.params.push(parse_quote!(Ctx));
+            generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote!(Ctx: Clone));
+        }
+        let predicates = match container_bound {
+            Some(predicates) => predicates.clone(),
+            None => {
+                let type_params: Vec<_> = generics
+                    .type_params()
+                    .map(|param| param.ident.clone())
+                    .collect();
+                self.variants
+                    .iter()
+                    .flat_map(|variant| &variant.fields)
+                    .flat_map(|field| {
+                        let (bound, ctx_type) = if is_encode {
+                            (&field.encode_bound, &field.encode_ctx_type)
+                        } else {
+                            (&field.decode_bound, &field.decode_ctx_type)
+                        };
+                        let ty = &field.ty;
+                        match (bound, ctx_type) {
+                            (Some(predicates), _) => predicates.clone(),
+                            (None, Some(ctx_type))
+                                if !mentions_any_ident(ty.to_token_stream(), &type_params)
+                                    && !mentions_any_ident(ctx_type.clone(), &type_params) =>
+                            {
+                                Vec::new()
+                            }
+                            (None, None)
+                                if !mentions_any_ident(ty.to_token_stream(), &type_params) =>
+                            {
+                                Vec::new()
+                            }
+                            (None, Some(ctx_type)) => {
+                                vec![parse_quote!(#ty: #trait_path<#ctx_type>)]
+                            }
+                            (None, None) => Vec::new(),
+                        }
+                    })
+                    .collect()
+            }
+        };
+        generics.make_where_clause().predicates.extend(predicates);
+        generics
+    }
+
+    fn encode_body(&self, writer_binding: &TokenStream) -> TokenStream {
+        let variant_arm = self.variants.iter().map(|variant| {
+            variant.encode_arm(
+                self.id_type.as_ref(),
+                &self.id_encode_ctx,
+                self.id_check_expr.as_ref(),
+                &self.crate_path,
+                writer_binding,
+            )
+        });
+
+        quote! {
+            match self {
+                #( #variant_arm, )*
+            }
         }
     }
 
@@ -281,29 +592,41 @@ impl ContainerData {
             decode_ctx_type,
             ..
         } = self;
-        let Self {
-            id_decoder,
-            id_decode_ctx,
-            id_decode_expr,
-            ..
-        } = self;
-        let (impl_generics, ident_generics, where_clause) = self.generics.split_for_impl();
+        let generics = self.generics_with_bounds(false);
+        let (impl_generics, _, where_clause) = generics.split_for_impl();
+        let (_, ident_generics, _) = self.generics.split_for_impl();
         let reader_binding: TokenStream = quote!(__declio_reader);
 
-        let variant_arm = self
-            .variants
-            .iter()
-            .map(|variant| variant.decode_arm(&self.crate_path, &reader_binding));
-
-        let id_decode_expr = match (id_decoder, id_decode_expr) {
-            (Some(decoder), None) => quote! {
-                #decoder(#id_decode_ctx, #reader_binding)
-                    .map_err(|e| #crate_path::Error::with_context("error decoding enum id", e))?
-            },
-            (None, Some(decode_expr)) => quote!(#decode_expr),
-            _ => unreachable!(),
+        let body = match &self.pod_endian {
+            Some(endian) => self.variants[0].pod_decode_body(
+                endian,
+                &ident.to_string(),
+                crate_path,
+                &reader_binding,
+            ),
+            None => self.decode_body(&reader_binding),
         };
 
+        // With default context values, the type can also be decoded without context.
+        let default_impl = self.decode_ctx_default.as_ref().map(|default| {
+            quote! {
+                impl #impl_generics #crate_path::Decode<()> for #ident #ident_generics
+                    #where_clause
+                {
+                    fn decode<R>(_: (), #reader_binding: &mut R)
+                        -> Result<Self, #crate_path::Error>
+                    where
+                        R: #crate_path::export::io::Read,
+                    {
+                        <Self as #crate_path::Decode<#decode_ctx_type>>::decode(
+                            #default,
+                            #reader_binding,
+                        )
+                    }
+                }
+            }
+        });
+
         quote! {
             impl #impl_generics #crate_path::Decode<#decode_ctx_type> for #ident #ident_generics
                 #where_clause
@@ -313,14 +636,108 @@ impl ContainerData {
                 where
                     R: #crate_path::export::io::Read,
                 {
-                    match #id_decode_expr {
-                        #( #variant_arm )*
-                        _ => Err(#crate_path::Error::new("unknown id value")),
+                    #body
+                }
+            }
+
+            #default_impl
+        }
+    }
+
+    fn layout_impl(&self) -> TokenStream {
+        let Self {
+            ident,
+            crate_path,
+            text,
+            ..
+        } = self;
+        let (impl_generics, ident_generics, where_clause) = self.generics.split_for_impl();
+        let layout = quote!(#crate_path::layout);
+
+        let name = ident.to_string();
+        let kind = if text.is_enum {
+            quote!(#layout::ContainerKind::Enum)
+        } else {
+            quote!(#layout::ContainerKind::Struct)
+        };
+        let encode_ctx = option_tokens(text.encode_ctx.as_ref());
+        let decode_ctx = option_tokens(text.decode_ctx.as_ref());
+        let id_type = option_tokens(text.id_type.as_ref());
+        let id_size = option_tokens(self.id_size.as_ref());
+        let variant = self
+            .variants
+            .iter()
+            .map(|variant| variant.layout(crate_path));
+
+        quote! {
+            impl #impl_generics #layout::Layout for #ident #ident_generics
+                #where_clause
+            {
+                fn layout() -> #layout::ContainerLayout {
+                    #layout::ContainerLayout {
+                        name: #name,
+                        kind: #kind,
+                        encode_ctx: #encode_ctx,
+                        decode_ctx: #decode_ctx,
+                        id_type: #id_type,
+                        id_size: #id_size,
+                        variants: #crate_path::export::vec![ #( #variant, )* ],
                     }
                 }
             }
         }
     }
+
+    fn decode_body(&self, reader_binding: &TokenStream) -> TokenStream {
+        let Self {
+            crate_path,
+            id_type,
+            id_decode_ctx,
+            id_decode_expr,
+            ..
+        } = self;
+
+        let variant_arm = self
+            .variants
+            .iter()
+            .map(|variant| variant.decode_arm(crate_path, reader_binding));
+
+        let trace = quote!(#crate_path::__private::trace);
+        let id_decode_expr = match (id_type, id_decode_expr) {
+            (Some(id_type), None) => {
+                let trace_value = trace_value(crate_path, &quote!(__declio_id));
+                quote! {
+                    {
+                        let __declio_span = #trace::SpanGuard::enter(__declio_tracing, #trace::SpanKind::Id, "id");
+                        let __declio_id = #crate_path::__private::decode(
+                            #id_decode_ctx,
+                            #crate_path::export::marker::PhantomData::<#id_type>,
+                            #reader_binding,
+                        )
+                            .map_err(|e| {
+                                #crate_path::Error::with_context("error decoding enum id", e)
+                            })?;
+                        __declio_span.exit(#trace_value);
+                        __declio_id
+                    }
+                }
+            }
+            (None, Some(decode_expr)) => quote!(#decode_expr),
+            _ => unreachable!(),
+        };
+        let name = self.ident.to_string();
+
+        quote! {
+            let _depth_guard = #crate_path::DepthGuard::enter()?;
+            let __declio_tracing = #trace::active();
+            let __declio_type_span =
+                #trace::SpanGuard::enter(__declio_tracing, #trace::SpanKind::Type, #name);
+            match #id_decode_expr {
+                #( #variant_arm )*
+                _ => Err(#crate_path::Error::new("unknown id value")),
+            }
+        }
+    }
 }
 
 #[derive(FromVariant)]
@@ -334,6 +751,7 @@ struct VariantReceiver {
 
 struct VariantData {
     ident: Option<syn::Ident>,
+    id_text: Option<String>,
     id_expr: TokenStream,
     id_pat: TokenStream,
     style: ast::Style,
@@ -341,10 +759,16 @@ struct VariantData {
 }
 
 impl VariantReceiver {
-    fn validate(&self, crate_path: &syn::Path) -> Result<VariantData, Error> {
+    fn validate(
+        &self,
+        crate_path: &syn::Path,
+        endian: Option<&DefaultEndian>,
+        inherit_ctx: InheritCtx,
+    ) -> Result<VariantData, Error> {
         let mut errors = Vec::new();
 
         let ident = Some(self.ident.clone());
+        let id_text = Some(self.id.value());
 
         let id_expr = match self.id.parse() {
             Ok(expr) => expr,
@@ -358,15 +782,21 @@ impl VariantReceiver {
 
         let style = self.fields.style;
 
+        let endian_ready = endian.map_or(0, |endian| endian.ready_index(&self.fields));
         let fields = self
             .fields
             .iter()
             .enumerate()
-            .flat_map(|(index, field)| match field.validate(crate_path, index) {
-                Ok(field) => Some(field),
-                Err(error) => {
-                    errors.push(error);
-                    None
+            .flat_map(|(index, field)| {
+                let endian = endian
+                    .filter(|_| index >= endian_ready)
+                    .map(|endian| (&endian.expr, endian.text.as_str()));
+                match field.validate(crate_path, endian, inherit_ctx, index) {
+                    Ok(field) => Some(field),
+                    Err(error) => {
+                        errors.push(error);
+                        None
+                    }
                 }
             })
             .collect();
@@ -374,6 +804,7 @@ impl VariantReceiver {
         if errors.is_empty() {
             Ok(VariantData {
                 ident,
+                id_text,
                 id_expr,
                 id_pat,
                 style,
@@ -389,22 +820,31 @@ impl VariantData {
     fn from_struct(
         fields: &ast::Fields<FieldReceiver>,
         crate_path: &syn::Path,
+        endian: Option<&DefaultEndian>,
+        inherit_ctx: InheritCtx,
     ) -> Result<VariantData, Error> {
         let mut errors = Vec::new();
 
         let ident = None;
+        let id_text = None;
         let id_expr = quote!(());
         let id_pat = quote!(_);
         let style = fields.style;
 
+        let endian_ready = endian.map_or(0, |endian| endian.ready_index(fields));
         let fields = fields
             .iter()
             .enumerate()
-            .flat_map(|(index, field)| match field.validate(crate_path, index) {
-                Ok(field) => Some(field),
-                Err(error) => {
-                    errors.push(error);
-                    None
+            .flat_map(|(index, field)| {
+                let endian = endian
+                    .filter(|_| index >= endian_ready)
+                    .map(|endian| (&endian.expr, endian.text.as_str()));
+                match field.validate(crate_path, endian, inherit_ctx, index) {
+                    Ok(field) => Some(field),
+                    Err(error) => {
+                        errors.push(error);
+                        None
+                    }
                 }
             })
             .collect();
@@ -412,6 +852,7 @@ impl VariantData {
         if errors.is_empty() {
             Ok(VariantData {
                 ident,
+                id_text,
                 id_expr,
                 id_pat,
                 style,
@@ -424,7 +865,7 @@ impl VariantData {
 
     fn encode_arm(
         &self,
-        id_encoder: Option<&TokenStream>,
+        id_type: Option<&TokenStream>,
         id_encode_ctx: &TokenStream,
         id_check_expr: Option<&TokenStream>,
         crate_path: &syn::Path,
@@ -432,27 +873,7 @@ impl VariantData {
     ) -> TokenStream {
         let Self { id_expr, .. } = self;
 
-        let path = match &self.ident {
-            Some(ident) => quote!(Self::#ident),
-            None => quote!(Self),
-        };
-
-        let field_pat = self.fields.iter().map(|field| {
-            let FieldData {
-                stored_ident,
-                public_ref_ident,
-                ..
-            } = field;
-            match stored_ident {
-                Some(stored_ident) => quote!(#stored_ident: #public_ref_ident),
-                None => quote!(#public_ref_ident),
-            }
-        });
-        let pat_fields = match self.style {
-            ast::Style::Tuple => quote!( ( #( #field_pat, )* ) ),
-            ast::Style::Struct => quote!( { #( #field_pat, )* } ),
-            ast::Style::Unit => quote!(),
-        };
+        let pattern = self.pattern();
 
         let id_check_stmt = id_check_expr.map(|check_value| {
             quote! {
@@ -461,9 +882,10 @@ impl VariantData {
                 }
             }
         });
-        let id_encode_stmt = id_encoder.map(|encoder| {
+        let id_encode_stmt = id_type.map(|id_type| {
             quote! {
-                #encoder(&(#id_expr), #id_encode_ctx, #writer_binding)
+                let __declio_id: #id_type = #id_expr;
+                #crate_path::__private::encode(#id_encode_ctx, &__declio_id, #writer_binding)
                     .map_err(|e| #crate_path::Error::with_context("error encoding enum id", e))?;
             }
         });
@@ -474,7 +896,7 @@ impl VariantData {
             .map(|field| field.encode_expr(crate_path, writer_binding));
 
         quote! {
-            #path #pat_fields => {
+            #pattern => {
                 #id_check_stmt
                 #id_encode_stmt
                 #( #field_encode_expr; )*
@@ -493,11 +915,163 @@ impl VariantData {
             .iter()
             .map(|field| field.decode_expr(crate_path, reader_binding));
 
-        let path = match &self.ident {
-            Some(ident) => quote!(Self::#ident),
-            None => quote!(Self),
-        };
+        let constructor = self.constructor();
+        let trace_variant = self.ident.as_ref().map(|ident| {
+            let name = ident.to_string();
+            quote!(__declio_type_span.variant(#name);)
+        });
+
+        quote! {
+            #id_pat => {
+                #trace_variant
+                #(
+                    let #private_owned_ident = #field_decode_expr;
+                    #[allow(unused_variables)]
+                    let #public_ref_ident = &#private_owned_ident;
+                )*
+                __declio_type_span.exit(|| None);
+                Ok(#constructor)
+            }
+        }
+    }
+
+    fn pod_encode_body(
+        &self,
+        endian: &TokenStream,
+        crate_path: &syn::Path,
+        writer_binding: &TokenStream,
+    ) -> TokenStream {
+        let pattern = self.pattern();
+        let public_ref_ident = self.fields.iter().map(|field| &field.public_ref_ident);
+        let (size, range) = self.pod_layout(crate_path);
+
+        quote! {
+            let #pattern = self;
+            let __declio_endian: #crate_path::ctx::Endian = #endian;
+            let mut __declio_bytes = [0u8; #size];
+            #(
+                #crate_path::Pod::write_pod(
+                    #public_ref_ident,
+                    &mut __declio_bytes[#range],
+                    __declio_endian,
+                );
+            )*
+            #writer_binding.write_all(&__declio_bytes)?;
+            Ok(())
+        }
+    }
+
+    fn pod_decode_body(
+        &self,
+        endian: &TokenStream,
+        name: &str,
+        crate_path: &syn::Path,
+        reader_binding: &TokenStream,
+    ) -> TokenStream {
+        let constructor = self.constructor();
+        let private_owned_ident = self.fields.iter().map(|field| &field.private_owned_ident);
+        let ty = self.fields.iter().map(|field| &field.ty);
+        let (size, range) = self.pod_layout(crate_path);
+        let trace = quote!(#crate_path::__private::trace);
 
+        quote! {
+            let _depth_guard = #crate_path::DepthGuard::enter()?;
+            let __declio_tracing = #trace::active();
+            let __declio_type_span =
+                #trace::SpanGuard::enter(__declio_tracing, #trace::SpanKind::Type, #name);
+            let __declio_endian: #crate_path::ctx::Endian = #endian;
+            let mut __declio_bytes = [0u8; #size];
+            #reader_binding.read_exact(&mut __declio_bytes)?;
+            #(
+                let #private_owned_ident =
+                    <#ty as #crate_path::Pod>::read_pod(&__declio_bytes[#range], __declio_endian);
+            )*
+            __declio_type_span.exit(|| None);
+            Ok(#constructor)
+        }
+    }
+
+    /// Returns the total size of a `pod` variant, and the byte range of each field, as constant
+    /// expressions.
+    fn pod_layout(&self, crate_path: &syn::Path) -> (TokenStream, Vec<TokenStream>) {
+        let mut offset = quote!(0);
+        let mut ranges = Vec::new();
+        for field in &self.fields {
+            let ty = &field.ty;
+            let end = quote!(#offset + <#ty as #crate_path::Pod>::SIZE);
+            ranges.push(quote!(#offset..#end));
+            offset = end;
+        }
+        (offset, ranges)
+    }
+
+    /// An expression that constructs the `VariantLayout` of this variant.
+    fn layout(&self, crate_path: &syn::Path) -> TokenStream {
+        let layout = quote!(#crate_path::layout);
+        let name = option_tokens(self.ident.as_ref().map(|ident| ident.to_string()).as_ref());
+        let id = option_tokens(self.id_text.as_ref());
+        let field = self.fields.iter().map(|field| {
+            let FieldText {
+                name,
+                ty,
+                encode_ctx,
+                decode_ctx,
+                with,
+                skip_if,
+                byte_len,
+            } = &field.text;
+            let encode_ctx = option_tokens(encode_ctx.as_ref());
+            let decode_ctx = option_tokens(decode_ctx.as_ref());
+            let with = option_tokens(with.as_ref());
+            let skip_if = option_tokens(skip_if.as_ref());
+            let byte_len = option_tokens(byte_len.as_ref());
+            let size = option_tokens(field.size.as_ref());
+            quote! {
+                #layout::FieldLayout {
+                    name: #name,
+                    ty: #ty,
+                    encode_ctx: #encode_ctx,
+                    decode_ctx: #decode_ctx,
+                    with: #with,
+                    skip_if: #skip_if,
+                    byte_len: #byte_len,
+                    size: #size,
+                }
+            }
+        });
+        quote! {
+            #layout::VariantLayout {
+                name: #name,
+                id: #id,
+                fields: #crate_path::export::vec![ #( #field, )* ],
+            }
+        }
+    }
+
+    /// A pattern that matches this variant, binding references to its fields.
+    fn pattern(&self) -> TokenStream {
+        let path = self.path();
+        let field_pat = self.fields.iter().map(|field| {
+            let FieldData {
+                stored_ident,
+                public_ref_ident,
+                ..
+            } = field;
+            match stored_ident {
+                Some(stored_ident) => quote!(#stored_ident: #public_ref_ident),
+                None => quote!(#public_ref_ident),
+            }
+        });
+        match self.style {
+            ast::Style::Tuple => quote!( #path ( #( #field_pat, )* ) ),
+            ast::Style::Struct => quote!( #path { #( #field_pat, )* } ),
+            ast::Style::Unit => quote!(#path),
+        }
+    }
+
+    /// An expression that constructs this variant from its decoded fields.
+    fn constructor(&self) -> TokenStream {
+        let path = self.path();
         let field_cons = self.fields.iter().map(|field| {
             let FieldData {
                 stored_ident,
@@ -509,21 +1083,17 @@ impl VariantData {
                 None => quote!(#private_owned_ident),
             }
         });
-        let cons_fields = match self.style {
-            ast::Style::Tuple => quote!( ( #( #field_cons, )* ) ),
-            ast::Style::Struct => quote!( { #( #field_cons, )* } ),
-            ast::Style::Unit => quote!(),
-        };
+        match self.style {
+            ast::Style::Tuple => quote!( #path ( #( #field_cons, )* ) ),
+            ast::Style::Struct => quote!( #path { #( #field_cons, )* } ),
+            ast::Style::Unit => quote!(#path),
+        }
+    }
 
-        quote! {
-            #id_pat => {
-                #(
-                    let #private_owned_ident = #field_decode_expr;
-                    #[allow(unused_variables)]
-                    let #public_ref_ident = &#private_owned_ident;
-                )*
-                Ok(#path #cons_fields)
-            }
+    fn path(&self) -> TokenStream {
+        match &self.ident {
+            Some(ident) => quote!(Self::#ident),
+            None => quote!(Self),
         }
     }
 }
@@ -548,24 +1118,264 @@ struct FieldReceiver {
 
     #[darling(default)]
     skip_if: Option<syn::LitStr>,
+
+    #[darling(default)]
+    byte_len: Option<syn::LitStr>,
+
+    #[darling(default)]
+    byte_len_pad: bool,
+
+    #[darling(default)]
+    endian: Option<FieldEndian>,
+
+    #[darling(default)]
+    bound: Asym<syn::LitStr>,
+}
+
+/// A context field in the container-level `ctx` attribute, like `len: usize`, with an optional
+/// default value, like `endian: Endian = Endian::Big`.
+struct CtxBinding {
+    pat: syn::Pat,
+    ty: syn::Type,
+    default: Option<syn::Expr>,
+}
+
+impl syn::parse::Parse for CtxBinding {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let pat = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let ty = input.parse()?;
+        let default = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { pat, ty, default })
+    }
+}
+
+/// Whether the context of the `Encode` and `Decode` impls is generic, from the container-level
+/// `ctx = "inherit"` attribute.
+#[derive(Clone, Copy)]
+struct InheritCtx {
+    encode: bool,
+    decode: bool,
+}
+
+impl InheritCtx {
+    fn any(self) -> bool {
+        self.encode || self.decode
+    }
+}
+
+/// The default endianness of fields, from the container-level `endian` or `endian_from` attribute.
+struct DefaultEndian {
+    /// An expression of type `Endian`.
+    expr: TokenStream,
+    /// The expression as it is shown in the `Layout` impl.
+    text: String,
+    /// The expression given by `endian_from`, which may refer to fields.
+    source: TokenStream,
+}
+
+impl DefaultEndian {
+    /// Creates a default endianness that does not depend on any fields.
+    fn new(expr: TokenStream, text: String) -> Self {
+        Self {
+            expr,
+            text,
+            source: TokenStream::new(),
+        }
+    }
+
+    /// Returns the index of the first field that can use the endianness, which is the field after
+    /// the last one that the expression refers to.
+    fn ready_index(&self, fields: &ast::Fields<FieldReceiver>) -> usize {
+        fields
+            .iter()
+            .enumerate()
+            .filter(|(index, field)| {
+                let name = match &field.ident {
+                    Some(ident) => ident.clone(),
+                    None => format_ident!("field_{}", index),
+                };
+                mentions_ident(self.source.clone(), &name)
+            })
+            .map(|(index, _)| index + 1)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Returns the context passed to a field's encoder or decoder, given the value of its `ctx`
+/// attribute, along with the type of the context if it is known, and the context as it is shown
+/// in the `Layout` impl.
+fn field_ctx(
+    arg: Option<&syn::LitStr>,
+    endian: Option<&(TokenStream, String)>,
+    inherit_ctx: bool,
+    crate_path: &syn::Path,
+    errors: &mut Vec<Error>,
+) -> (TokenStream, Option<TokenStream>, Option<String>) {
+    match arg {
+        Some(lit) if lit.value() == "inherit" => {
+            if !inherit_ctx {
+                errors.push(
+                    Error::custom(
+                        "`ctx = \"inherit\"` requires a container-level `ctx = \"inherit\"`",
+                    )
+                    .with_span(lit),
+                );
+            }
+            (quote!(ctx.clone()), Some(quote!(Ctx)), Some("ctx".into()))
+        }
+        Some(lit) => match lit.parse() {
+            Ok(expr) => (expr, None, Some(lit.value())),
+            Err(err) => {
+                errors.push(from_syn_error(err));
+                (quote!(unreachable!("compile error")), None, None)
+            }
+        },
+        None => match endian {
+            Some((expr, text)) => (
+                expr.clone(),
+                Some(quote!(#crate_path::ctx::Endian)),
+                Some(text.clone()),
+            ),
+            None if inherit_ctx => (quote!(ctx.clone()), Some(quote!(Ctx)), Some("ctx".into())),
+            None => (quote!(()), Some(quote!(())), None),
+        },
+    }
+}
+
+/// Whether the token stream contains any of the given identifiers, like the type parameters of a
+/// container.
+fn mentions_any_ident(tokens: TokenStream, idents: &[syn::Ident]) -> bool {
+    idents
+        .iter()
+        .any(|ident| mentions_ident(tokens.clone(), ident))
+}
+
+/// Whether the token stream contains the given identifier.
+fn mentions_ident(tokens: TokenStream, ident: &syn::Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(other) => other == *ident,
+        proc_macro2::TokenTree::Group(group) => mentions_ident(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// The value of the field-level `endian` attribute.
+enum FieldEndian {
+    /// `#[declio(endian)]`, which uses the container's endianness.
+    Inherit,
+    /// `#[declio(endian = "...")]`, which overrides the container's endianness.
+    Override(syn::LitStr),
+}
+
+impl FromMeta for FieldEndian {
+    fn from_word() -> Result<Self, Error> {
+        Ok(Self::Inherit)
+    }
+
+    fn from_value(value: &syn::Lit) -> Result<Self, Error> {
+        match value {
+            syn::Lit::Str(lit) => Ok(Self::Override(lit.clone())),
+            _ => Err(Error::unexpected_lit_type(value)),
+        }
+    }
 }
 
 struct FieldData {
+    ty: syn::Type,
+    encode_ctx_type: Option<TokenStream>,
+    decode_ctx_type: Option<TokenStream>,
+    encode_bound: Option<Vec<syn::WherePredicate>>,
+    decode_bound: Option<Vec<syn::WherePredicate>>,
     stored_ident: Option<syn::Ident>,
     public_ref_ident: syn::Ident,
     private_owned_ident: syn::Ident,
     encode_ctx: TokenStream,
     decode_ctx: TokenStream,
-    encoder: TokenStream,
-    decoder: TokenStream,
+    /// The `with` or `encode_with` function, if the field type's `Encode` impl is not used.
+    encoder: Option<TokenStream>,
+    /// The `with` or `decode_with` function, if the field type's `Decode` impl is not used.
+    decoder: Option<TokenStream>,
     skip_if: Option<TokenStream>,
+    byte_len: Option<TokenStream>,
+    byte_len_pad: bool,
+    text: FieldText,
+    /// An expression for the size of the field in bytes, if it is known statically.
+    size: Option<TokenStream>,
+}
+
+/// The attributes of a field as source text, for the `Layout` impl.
+struct FieldText {
+    name: String,
+    ty: String,
+    encode_ctx: Option<String>,
+    decode_ctx: Option<String>,
+    with: Option<String>,
+    skip_if: Option<String>,
+    byte_len: Option<String>,
 }
 
 impl FieldReceiver {
-    fn validate(&self, crate_path: &syn::Path, index: usize) -> Result<FieldData, Error> {
+    /// Whether any attributes are set that change how the field is encoded or decoded.
+    fn has_codec_attrs(&self) -> bool {
+        self.ctx.is_some()
+            || self.with.is_some()
+            || self.encode_with.is_some()
+            || self.decode_with.is_some()
+            || self.skip_if.is_some()
+            || self.byte_len.is_some()
+            || self.endian.is_some()
+    }
+
+    fn validate(
+        &self,
+        crate_path: &syn::Path,
+        container_endian: Option<(&TokenStream, &str)>,
+        inherit_ctx: InheritCtx,
+        index: usize,
+    ) -> Result<FieldData, Error> {
         let Self { ty, .. } = self;
         let mut errors = Vec::new();
 
+        let has_with =
+            self.with.is_some() || self.encode_with.is_some() || self.decode_with.is_some();
+        let endian = match &self.endian {
+            Some(FieldEndian::Inherit) => match container_endian {
+                Some((expr, text)) => Some((expr.clone(), text.to_owned())),
+                None => {
+                    errors.push(Error::custom(
+                        "`endian` without a value requires a container-level `endian`, or an \
+                         `endian_from` that only depends on previous fields",
+                    ));
+                    None
+                }
+            },
+            Some(FieldEndian::Override(lit)) => match parse_endian(lit, crate_path) {
+                Ok(expr) => Some((expr, endian_text(lit))),
+                Err(error) => {
+                    errors.push(error);
+                    None
+                }
+            },
+            None if !has_with && is_primitive(ty) => {
+                container_endian.map(|(expr, text)| (expr.clone(), text.to_owned()))
+            }
+            None => None,
+        };
+        if self.endian.is_some() && self.ctx.is_some() {
+            errors.push(Error::custom(
+                "`endian` and `ctx` are incompatible with each other",
+            ));
+        }
+        let encode_bound = parse_bound(self.bound.encode(), &mut errors);
+        let decode_bound = parse_bound(self.bound.decode(), &mut errors);
+
         let stored_ident = self.ident.clone();
         let public_ref_ident = match &self.ident {
             Some(ident) => ident.clone(),
@@ -573,49 +1383,47 @@ impl FieldReceiver {
         };
         let private_owned_ident = format_ident!("__declio_owned_{}", public_ref_ident);
 
-        let encode_ctx = match self.ctx.encode() {
-            Some(lit) => match lit.parse() {
-                Ok(expr) => expr,
-                Err(err) => {
-                    errors.push(from_syn_error(err));
-                    quote!(unreachable!("compile error"))
-                }
-            },
-            None => quote!(()),
-        };
-
-        let decode_ctx = match self.ctx.decode() {
-            Some(lit) => match lit.parse() {
-                Ok(expr) => expr,
-                Err(err) => {
-                    errors.push(from_syn_error(err));
-                    quote!(unreachable!("compile error"))
-                }
-            },
-            None => quote!(()),
-        };
+        let (encode_ctx, encode_ctx_type, encode_ctx_text) = field_ctx(
+            self.ctx.encode(),
+            endian.as_ref(),
+            inherit_ctx.encode,
+            crate_path,
+            &mut errors,
+        );
+        let (decode_ctx, decode_ctx_type, decode_ctx_text) = field_ctx(
+            self.ctx.decode(),
+            endian.as_ref(),
+            inherit_ctx.decode,
+            crate_path,
+            &mut errors,
+        );
+        // Bounds can only be inferred for the field type's own `Encode` and `Decode` impls.
+        let encode_ctx_type =
+            encode_ctx_type.filter(|_| self.encode_with.is_none() && self.with.is_none());
+        let decode_ctx_type =
+            decode_ctx_type.filter(|_| self.decode_with.is_none() && self.with.is_none());
 
         let encoder = match (&self.encode_with, &self.with) {
-            (None, None) => quote!(<#ty as #crate_path::Encode<_>>::encode),
-            (Some(encode_with), None) => quote!(#encode_with),
-            (None, Some(with)) => quote!(#with::encode),
+            (None, None) => None,
+            (Some(encode_with), None) => Some(quote!(#encode_with)),
+            (None, Some(with)) => Some(quote!(#with::encode)),
             _ => {
                 errors.push(Error::custom(
                     "`encode_with` and `with` are incompatible with each other",
                 ));
-                quote!(__compile_error_throwaway)
+                None
             }
         };
 
         let decoder = match (&self.decode_with, &self.with) {
-            (None, None) => quote!(<#ty as #crate_path::Decode<_>>::decode),
-            (Some(decode_with), None) => quote!(#decode_with),
-            (None, Some(with)) => quote!(#with::decode),
+            (None, None) => None,
+            (Some(decode_with), None) => Some(quote!(#decode_with)),
+            (None, Some(with)) => Some(quote!(#with::decode)),
             _ => {
                 errors.push(Error::custom(
                     "`decode_with` and `with` are incompatible with each other",
                 ));
-                quote!(__compile_error_throwaway)
+                None
             }
         };
 
@@ -630,8 +1438,66 @@ impl FieldReceiver {
             None => None,
         };
 
+        let byte_len = match &self.byte_len {
+            Some(lit) => match lit.parse() {
+                Ok(expr) => Some(expr),
+                Err(error) => {
+                    errors.push(from_syn_error(error));
+                    Some(quote!(unreachable!("compile error")))
+                }
+            },
+            None => None,
+        };
+
+        if self.byte_len_pad && self.byte_len.is_none() {
+            errors.push(Error::custom("`byte_len_pad` requires `byte_len`"));
+        }
+
+        let with_text: Vec<_> = [
+            (&self.with, ""),
+            (&self.encode_with, " (encode)"),
+            (&self.decode_with, " (decode)"),
+        ]
+        .iter()
+        .flat_map(|(path, suffix)| {
+            path.as_ref()
+                .map(|path| format!("{}{}", tokens_text(path), suffix))
+        })
+        .collect();
+        let text = FieldText {
+            name: match &self.ident {
+                Some(ident) => ident.to_string(),
+                None => index.to_string(),
+            },
+            ty: tokens_text(ty),
+            encode_ctx: encode_ctx_text,
+            decode_ctx: decode_ctx_text,
+            with: Some(with_text.join(", ")).filter(|text| !text.is_empty()),
+            skip_if: self.skip_if.as_ref().map(syn::LitStr::value),
+            byte_len: self.byte_len.as_ref().map(syn::LitStr::value),
+        };
+
+        let byte_len_size = self
+            .byte_len
+            .as_ref()
+            .and_then(|lit| lit.parse::<syn::LitInt>().ok());
+        let size = if self.skip_if.is_some() || has_with {
+            None
+        } else if let Some(size) = byte_len_size {
+            Some(quote!(#size))
+        } else if self.byte_len.is_none() && is_primitive(ty) {
+            Some(quote!(#crate_path::export::mem::size_of::<#ty>()))
+        } else {
+            None
+        };
+
         if errors.is_empty() {
             Ok(FieldData {
+                ty: ty.clone(),
+                encode_ctx_type,
+                decode_ctx_type,
+                encode_bound,
+                decode_bound,
                 stored_ident,
                 public_ref_ident,
                 private_owned_ident,
@@ -640,6 +1506,10 @@ impl FieldReceiver {
                 encoder,
                 decoder,
                 skip_if,
+                byte_len,
+                byte_len_pad: self.byte_len_pad,
+                text,
+                size,
             })
         } else {
             Err(Error::multiple(errors))
@@ -651,20 +1521,36 @@ impl FieldData {
     fn encode_expr(&self, crate_path: &syn::Path, writer_binding: &TokenStream) -> TokenStream {
         let Self {
             public_ref_ident,
-            encoder,
             encode_ctx,
             ..
         } = self;
         let error_context = format!("error encoding field {}", public_ref_ident);
+        let encode_call = match &self.encoder {
+            Some(encoder) => quote!(#encoder(#public_ref_ident, #encode_ctx, #writer_binding)),
+            None => quote! {
+                #crate_path::__private::encode(#encode_ctx, #public_ref_ident, #writer_binding)
+            },
+        };
+        let call = match &self.byte_len {
+            Some(byte_len) => {
+                let pad = self.byte_len_pad;
+                quote! {
+                    #crate_path::util::encode_byte_len(
+                        #byte_len,
+                        #pad,
+                        #writer_binding,
+                        |#writer_binding| #encode_call,
+                    )
+                }
+            }
+            None => encode_call,
+        };
         let raw_encoder = quote! {
-            #encoder(#public_ref_ident, #encode_ctx, #writer_binding)
-                .map_err(|e| #crate_path::Error::with_context(#error_context, e))?
+            #call.map_err(|e| #crate_path::Error::with_context(#error_context, e))?
         };
         match &self.skip_if {
             Some(skip_if) => quote! {
-                if #skip_if {
-                    ()
-                } else {
+                if !(#skip_if) {
                     #raw_encoder
                 }
             },
@@ -674,15 +1560,47 @@ impl FieldData {
 
     fn decode_expr(&self, crate_path: &syn::Path, reader_binding: &TokenStream) -> TokenStream {
         let Self {
+            ty,
             public_ref_ident,
             decode_ctx,
-            decoder,
             ..
         } = self;
         let error_context = format!("error decoding field {}", public_ref_ident);
+        let decode_call = match &self.decoder {
+            Some(decoder) => quote!(#decoder(#decode_ctx, #reader_binding)),
+            None => quote! {
+                #crate_path::__private::decode(
+                    #decode_ctx,
+                    #crate_path::export::marker::PhantomData::<#ty>,
+                    #reader_binding,
+                )
+            },
+        };
+        let call = match &self.byte_len {
+            Some(byte_len) => {
+                let pad = self.byte_len_pad;
+                quote! {
+                    #crate_path::util::decode_byte_len(
+                        #byte_len,
+                        #pad,
+                        #reader_binding,
+                        |#reader_binding| #decode_call,
+                    )
+                }
+            }
+            None => decode_call,
+        };
+        let name = &self.text.name;
+        let trace = quote!(#crate_path::__private::trace);
+        let trace_value = trace_value(crate_path, &quote!(__declio_value));
         let raw_decoder = quote! {
-            #decoder(#decode_ctx, #reader_binding)
-                .map_err(|e| #crate_path::Error::with_context(#error_context, e))?
+            {
+                let __declio_span = #trace::SpanGuard::enter(__declio_tracing, #trace::SpanKind::Field, #name);
+                let __declio_value = #call
+                    .map_err(|e| #crate_path::Error::with_context(#error_context, e))?;
+                __declio_span.exit(#trace_value);
+                __declio_value
+            }
         };
         match &self.skip_if {
             Some(skip_if) => quote! {
@@ -787,6 +1705,100 @@ impl<T> Default for Asym<T> {
     }
 }
 
+/// Returns a closure that formats the value of `binding` for a trace, if its type implements
+/// `Debug`.
+fn trace_value(crate_path: &syn::Path, binding: &TokenStream) -> TokenStream {
+    quote! {
+        || {
+            use #crate_path::__private::trace::{TraceDebug as _, TraceNoDebug as _};
+            (&#crate_path::__private::trace::DebugValue(&#binding)).trace_debug()
+        }
+    }
+}
+
+/// Parses a `bound` attribute value, which is a comma-separated list of where predicates.
+fn parse_bound(
+    arg: Option<&syn::LitStr>,
+    errors: &mut Vec<Error>,
+) -> Option<Vec<syn::WherePredicate>> {
+    let lit = arg?;
+    match lit.parse_with(Punctuated::<syn::WherePredicate, Token![,]>::parse_terminated) {
+        Ok(predicates) => Some(predicates.into_iter().collect()),
+        Err(error) => {
+            errors.push(from_syn_error(error));
+            Some(Vec::new())
+        }
+    }
+}
+
+/// Converts an optional value into an expression of type `Option`.
+fn option_tokens<T: ToTokens>(value: Option<&T>) -> TokenStream {
+    match value {
+        Some(value) => quote!(Some(#value)),
+        None => quote!(None),
+    }
+}
+
+/// Formats tokens like a type or path as source text, without the spaces that `to_string` puts
+/// between all of the tokens.
+fn tokens_text<T: ToTokens>(tokens: &T) -> String {
+    const REPLACEMENTS: &[(&str, &str)] = &[
+        (" :: ", "::"),
+        (":: ", "::"),
+        (" < ", "<"),
+        ("< ", "<"),
+        (" <", "<"),
+        (" >", ">"),
+        ("& ", "&"),
+        (" ,", ","),
+        (" ;", ";"),
+        ("[ ", "["),
+        (" ]", "]"),
+        ("( ", "("),
+        (" )", ")"),
+    ];
+    let mut text = tokens.to_token_stream().to_string();
+    for (from, to) in REPLACEMENTS {
+        text = text.replace(from, to);
+    }
+    text
+}
+
+/// Returns an `endian` attribute value as it is shown in the `Layout` impl.
+fn endian_text(lit: &syn::LitStr) -> String {
+    match lit.value().as_str() {
+        "big" => "Endian::Big".into(),
+        "little" => "Endian::Little".into(),
+        other => other.into(),
+    }
+}
+
+/// Parses an `endian` attribute value, which is either `"big"`, `"little"` or an expression.
+fn parse_endian(lit: &syn::LitStr, crate_path: &syn::Path) -> Result<TokenStream, Error> {
+    match lit.value().as_str() {
+        "big" => Ok(quote!(#crate_path::ctx::Endian::Big)),
+        "little" => Ok(quote!(#crate_path::ctx::Endian::Little)),
+        _ => lit.parse().map_err(from_syn_error),
+    }
+}
+
+/// Whether the type is an integer or floating-point primitive, or an array of them.
+fn is_primitive(ty: &syn::Type) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128", "f32", "f64",
+    ];
+    match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path
+            .path
+            .get_ident()
+            .is_some_and(|ident| PRIMITIVES.iter().any(|prim| ident == prim)),
+        syn::Type::Array(array) => is_primitive(&array.elem),
+        syn::Type::Group(group) => is_primitive(&group.elem),
+        syn::Type::Paren(paren) => is_primitive(&paren.elem),
+        _ => false,
+    }
+}
+
 fn from_syn_error(err: syn::Error) -> Error {
     Error::custom(&err).with_span(&err.span())
 }
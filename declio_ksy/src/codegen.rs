@@ -0,0 +1,1195 @@
+//! Generation of Rust source code from a parsed Kaitai Struct specification.
+
+use crate::expr::{Expr, Kind, Scope};
+use crate::{Error, Unsupported};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt::Write;
+use yaml_rust2::Yaml;
+
+/// Attribute keys which are understood by the generator.
+const FIELD_KEYS: &[&str] = &[
+    "id",
+    "doc",
+    "doc-ref",
+    "-orig-id",
+    "type",
+    "size",
+    "size-eos",
+    "contents",
+    "repeat",
+    "repeat-expr",
+    "repeat-until",
+    "if",
+    "enum",
+    "encoding",
+    "terminator",
+];
+
+/// Attribute keys which change the meaning of a field, but don't prevent it from being decoded.
+const LOSSY_FIELD_KEYS: &[&str] = &["valid", "pad-right"];
+
+const TYPE_KEYS: &[&str] = &["meta", "doc", "doc-ref", "seq", "types", "enums"];
+
+const META_KEYS: &[&str] = &[
+    "id",
+    "title",
+    "application",
+    "file-extension",
+    "xref",
+    "license",
+    "ks-version",
+    "ks-debug",
+    "ks-opaque-types",
+    "tags",
+    "endian",
+    "bit-endian",
+    "encoding",
+];
+
+pub(crate) fn generate(
+    source: &str,
+    crate_path: &str,
+) -> Result<(String, Vec<Unsupported>), Error> {
+    let docs = yaml_rust2::YamlLoader::load_from_str(source)?;
+    let root = match docs.as_slice() {
+        [root @ Yaml::Hash(_)] => root,
+        _ => return Err(Error::Invalid("expected a single YAML mapping".into())),
+    };
+    let id = root["meta"]["id"]
+        .as_str()
+        .ok_or_else(|| Error::Invalid("missing `meta/id`".into()))?;
+
+    let mut gen = Generator {
+        crate_path,
+        endian: None,
+        encoding: None,
+        types: Vec::new(),
+        type_index: HashMap::new(),
+        enums: Vec::new(),
+        enum_index: HashMap::new(),
+        items: Vec::new(),
+        unsupported: Vec::new(),
+    };
+    gen.meta(root, "");
+    gen.collect(id, root, "");
+    for index in 0..gen.types.len() {
+        gen.struct_item(index);
+    }
+    for index in 0..gen.enums.len() {
+        gen.enum_item(index);
+    }
+
+    let mut code = format!(
+        "// Generated by declio_ksy from the `{}` Kaitai Struct specification. Do not edit.\n",
+        id
+    );
+    for item in &gen.items {
+        code.push('\n');
+        code.push_str(item);
+    }
+    Ok((code, gen.unsupported))
+}
+
+struct Generator<'a> {
+    crate_path: &'a str,
+    endian: Option<&'static str>,
+    encoding: Option<&'a str>,
+    types: Vec<TypeDef<'a>>,
+    type_index: HashMap<&'a str, usize>,
+    enums: Vec<EnumDef<'a>>,
+    enum_index: HashMap<&'a str, usize>,
+    items: Vec<String>,
+    unsupported: Vec<Unsupported>,
+}
+
+struct TypeDef<'a> {
+    rust_name: String,
+    spec: &'a Yaml,
+    path: String,
+}
+
+struct EnumDef<'a> {
+    rust_name: String,
+    values: &'a Yaml,
+    path: String,
+    /// The integer type of the enum, which is taken from the first field that uses it.
+    prim: Option<Primitive>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Primitive {
+    rust: &'static str,
+    size: usize,
+    is_int: bool,
+    endian: Option<&'static str>,
+}
+
+/// The context needed to encode or decode a single element of a field.
+enum ElemCtx {
+    None,
+    /// An `Endian`, either the container's (which applies automatically to primitives, but not to
+    /// enums), or an explicit one.
+    Endian {
+        explicit: Option<&'static str>,
+        automatic: bool,
+    },
+    Value(String),
+}
+
+/// The parts of a field (or of a single element of a repeated field).
+struct FieldType {
+    ty: String,
+    ctx: ElemCtx,
+    kind: Kind,
+    /// The item defining a type used only by this field, like a magic or a switch.
+    item: Option<String>,
+}
+
+/// What a translated expression is used for.
+#[derive(Clone, Copy)]
+enum Target {
+    /// An `i64` or `bool` value.
+    Value,
+    /// A `usize` length.
+    Len,
+    /// A `skip_if` condition, which is the negation of an `if` condition.
+    Skip,
+}
+
+/// The fields of the type currently being generated, as seen by expressions.
+struct FieldScope<'g, 'a> {
+    gen: &'g Generator<'a>,
+    fields: &'g [(&'a str, String, Kind)],
+}
+
+impl<'a> Generator<'a> {
+    fn report(&mut self, path: &str, message: impl Into<String>) {
+        self.unsupported.push(Unsupported {
+            path: path.to_owned(),
+            message: message.into(),
+        });
+    }
+
+    fn meta(&mut self, root: &'a Yaml, path: &str) {
+        let meta = match root["meta"].as_hash() {
+            Some(meta) => meta,
+            None => return,
+        };
+        for key in meta.keys() {
+            let key = key.as_str().unwrap_or_default();
+            if key == "imports" {
+                self.report(
+                    &format!("{}/meta/imports", path),
+                    "imports are not supported",
+                );
+            } else if !META_KEYS.contains(&key) {
+                self.report(&format!("{}/meta/{}", path, key), "unknown key");
+            }
+        }
+        match &root["meta"]["endian"] {
+            Yaml::String(endian) if endian == "le" => self.endian = Some("little"),
+            Yaml::String(endian) if endian == "be" => self.endian = Some("big"),
+            Yaml::BadValue => {}
+            _ => self.report(
+                &format!("{}/meta/endian", path),
+                "only a fixed `le` or `be` endianness is supported",
+            ),
+        }
+        self.encoding = root["meta"]["encoding"].as_str();
+    }
+
+    /// Registers a type and everything nested in it, so they can be referred to by name.
+    fn collect(&mut self, name: &'a str, spec: &'a Yaml, path: &str) {
+        if self.type_index.contains_key(name) {
+            self.report(path, format!("duplicate type name `{}`", name));
+            return;
+        }
+        self.type_index.insert(name, self.types.len());
+        self.types.push(TypeDef {
+            rust_name: type_name(name),
+            spec,
+            path: path.to_owned(),
+        });
+
+        if let Some(types) = spec["types"].as_hash() {
+            for (name, spec) in types {
+                let name = name.as_str().unwrap_or_default();
+                self.collect(name, spec, &format!("{}/types/{}", path, name));
+            }
+        }
+        if let Some(enums) = spec["enums"].as_hash() {
+            for (name, values) in enums {
+                let name = name.as_str().unwrap_or_default();
+                let path = format!("{}/enums/{}", path, name);
+                if self.enum_index.contains_key(name) {
+                    self.report(&path, format!("duplicate enum name `{}`", name));
+                    continue;
+                }
+                self.enum_index.insert(name, self.enums.len());
+                self.enums.push(EnumDef {
+                    rust_name: type_name(name),
+                    values,
+                    path,
+                    prim: None,
+                });
+            }
+        }
+    }
+
+    fn struct_item(&mut self, index: usize) {
+        let spec = self.types[index].spec;
+        let path = self.types[index].path.clone();
+        let rust_name = self.types[index].rust_name.clone();
+
+        if index > 0 && !spec["meta"].is_badvalue() {
+            self.report(
+                &format!("{}/meta", path),
+                "`meta` is only supported on the top-level type",
+            );
+        }
+        if let Some(keys) = spec.as_hash() {
+            for key in keys.keys() {
+                let key = key.as_str().unwrap_or_default();
+                match key {
+                    "params" => {
+                        self.report(&format!("{}/params", path), "parameters are not supported")
+                    }
+                    "instances" => self.report(
+                        &format!("{}/instances", path),
+                        "instances are not supported",
+                    ),
+                    _ if !TYPE_KEYS.contains(&key) => {
+                        self.report(&format!("{}/{}", path, key), "unknown key")
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut fields: Vec<(&'a str, String, Kind)> = Vec::new();
+        let mut body = String::new();
+        let mut extra_items = Vec::new();
+        let seq = spec["seq"].as_vec().map(Vec::as_slice).unwrap_or_default();
+        for (i, attr) in seq.iter().enumerate() {
+            let field_path = format!("{}/seq/{}", path, i);
+            let id = match attr["id"].as_str() {
+                Some(id) => id,
+                None => {
+                    self.report(&field_path, "fields without an `id` are not supported");
+                    continue;
+                }
+            };
+            match self.field(&rust_name, id, attr, &field_path, &fields) {
+                Some((text, kind, item)) => {
+                    body.push_str(&text);
+                    fields.push((id, field_name(id), kind));
+                    extra_items.extend(item);
+                }
+                None => fields.push((id, field_name(id), Kind::Other)),
+            }
+        }
+
+        let mut item = String::new();
+        write_doc(&mut item, "", &spec["doc"]);
+        writeln!(
+            item,
+            "#[derive(Debug, Clone, PartialEq, {0}::Encode, {0}::Decode)]",
+            self.crate_path
+        )
+        .unwrap();
+        let mut attrs = self.crate_attr();
+        if let Some(endian) = self.endian {
+            attrs.push(format!("endian = \"{}\"", endian));
+        }
+        write_attrs(&mut item, "", &attrs);
+        if body.is_empty() {
+            writeln!(item, "pub struct {};", rust_name).unwrap();
+        } else {
+            writeln!(item, "pub struct {} {{\n{}}}", rust_name, body).unwrap();
+        }
+        self.items.push(item);
+        self.items.extend(extra_items);
+    }
+
+    /// Generates a field, returning its text, its kind and any item it needs. Returns `None` if
+    /// the field can't be expressed.
+    fn field(
+        &mut self,
+        container: &str,
+        id: &'a str,
+        attr: &'a Yaml,
+        path: &str,
+        fields: &[(&'a str, String, Kind)],
+    ) -> Option<(String, Kind, Option<String>)> {
+        let mut supported = true;
+        for key in attr.as_hash()?.keys() {
+            let key = key.as_str().unwrap_or_default();
+            if LOSSY_FIELD_KEYS.contains(&key) {
+                self.report(
+                    &format!("{}/{}", path, key),
+                    format!("`{}` is not supported and was ignored", key),
+                );
+            } else if !FIELD_KEYS.contains(&key) {
+                self.report(
+                    &format!("{}/{}", path, key),
+                    format!("`{}` is not supported", key),
+                );
+                supported = false;
+            }
+        }
+        if !supported {
+            return None;
+        }
+
+        let skip_if = match &attr["if"] {
+            Yaml::BadValue => None,
+            cond => Some(self.expr(cond, fields, path, "if", Target::Skip)?),
+        };
+
+        let mut byte_len = None;
+        let mut field = match &attr["type"] {
+            _ if !attr["contents"].is_badvalue() => self.contents(container, id, attr, path)?,
+            Yaml::BadValue => self.bytes(attr, path, fields)?,
+            Yaml::String(ty) if ty == "str" || ty == "strz" => {
+                self.string(ty, attr, path, fields)?
+            }
+            Yaml::String(ty) => {
+                let field = self.named_type(ty, attr, path)?;
+                if let Some(size) = self.size(attr, path, fields, "type") {
+                    if !matches!(field.kind, Kind::User(_)) {
+                        self.report(
+                            &format!("{}/size", path),
+                            "`size` is only supported for user types",
+                        );
+                        return None;
+                    }
+                    byte_len = Some(size?);
+                }
+                field
+            }
+            Yaml::Hash(_) => {
+                let field = self.switch(container, id, attr, path, fields)?;
+                if let Some(size) = self.size(attr, path, fields, "type") {
+                    byte_len = Some(size?);
+                }
+                field
+            }
+            _ => {
+                self.report(
+                    &format!("{}/type", path),
+                    "expected a type name or a switch",
+                );
+                return None;
+            }
+        };
+
+        // Notes about differences from Kaitai Struct, appended to the field's documentation.
+        let mut note = None;
+        let repeat = match &attr["repeat"] {
+            Yaml::BadValue => None,
+            Yaml::String(repeat) if repeat == "expr" => {
+                let count = self.expr(
+                    &attr["repeat-expr"],
+                    fields,
+                    path,
+                    "repeat-expr",
+                    Target::Len,
+                )?;
+                Some(format!("{}::ctx::Len({})", self.crate_path, count))
+            }
+            Yaml::String(repeat) if repeat == "eos" => {
+                Some(format!("{}::ctx::ToEnd", self.crate_path))
+            }
+            Yaml::String(repeat) if repeat == "until" => {
+                let terminator = attr["repeat-until"]
+                    .as_str()
+                    .and_then(|until| Expr::parse(until).ok())
+                    .and_then(|until| match until {
+                        Expr::Binary(lhs, "==", rhs) => match (*lhs, *rhs) {
+                            (Expr::Name(name), Expr::Int(value))
+                            | (Expr::Int(value), Expr::Name(name))
+                                if name == "_" =>
+                            {
+                                Some(value)
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    });
+                match terminator {
+                    Some(_) if !attr["enum"].is_badvalue() => {
+                        self.report(
+                            &format!("{}/repeat-until", path),
+                            "conditions on enums are not supported",
+                        );
+                        return None;
+                    }
+                    Some(terminator) if field.kind == Kind::Int => {
+                        if !int_fits(&field.ty, terminator) {
+                            self.report(
+                                &format!("{}/repeat-until", path),
+                                format!("`{}` is out of range for `{}`", terminator, field.ty),
+                            );
+                            return None;
+                        }
+                        note = Some(format!(
+                            "Unlike in Kaitai Struct, the terminating `{}` is not included.",
+                            terminator
+                        ));
+                        Some(format!("{}::ctx::Until({})", self.crate_path, terminator))
+                    }
+                    _ => {
+                        self.report(
+                            &format!("{}/repeat-until", path),
+                            "only `_ == <integer>` conditions on integers are supported",
+                        );
+                        return None;
+                    }
+                }
+            }
+            _ => {
+                self.report(
+                    &format!("{}/repeat", path),
+                    "expected `expr`, `eos` or `until`",
+                );
+                return None;
+            }
+        };
+
+        let mut attrs = Vec::new();
+        let mut ty = field.ty;
+        if let Some(repeat) = repeat {
+            if byte_len.is_some() {
+                self.report(
+                    &format!("{}/size", path),
+                    "`size` is not supported on repeated fields",
+                );
+                return None;
+            }
+            let ctx = match field.ctx {
+                ElemCtx::None => repeat,
+                ElemCtx::Endian { explicit, .. } => {
+                    format!("({}, {})", repeat, self.endian_value(explicit))
+                }
+                ElemCtx::Value(ctx) => format!("({}, {})", repeat, ctx),
+            };
+            attrs.push(format!("ctx = \"{}\"", ctx));
+            ty = format!("Vec<{}>", ty);
+            field.kind = Kind::Sequence;
+        } else {
+            match field.ctx {
+                ElemCtx::None => {}
+                ElemCtx::Endian {
+                    explicit: Some(endian),
+                    ..
+                } => attrs.push(format!("endian = \"{}\"", endian)),
+                // The container's endian only applies automatically to bare primitives, not to
+                // the `Option` of a conditional field.
+                ElemCtx::Endian {
+                    explicit: None,
+                    automatic,
+                } => {
+                    if !automatic || skip_if.is_some() {
+                        attrs.push("endian".into());
+                    }
+                }
+                ElemCtx::Value(ctx) => attrs.push(format!("ctx = \"{}\"", ctx)),
+            }
+        }
+        if let Some(byte_len) = byte_len {
+            attrs.push(format!("byte_len = \"{}\"", byte_len));
+        }
+        if let Some(skip_if) = skip_if {
+            attrs.push(format!("skip_if = \"{}\"", skip_if));
+            ty = format!("Option<{}>", ty);
+            field.kind = Kind::Other;
+        }
+
+        let mut text = String::new();
+        write_doc(&mut text, "    ", &attr["doc"]);
+        if let Some(note) = note {
+            if attr["doc"].as_str().is_some() {
+                writeln!(text, "    ///").unwrap();
+            }
+            writeln!(text, "    /// {}", note).unwrap();
+        }
+        write_attrs(&mut text, "    ", &attrs);
+        writeln!(text, "    pub {}: {},", field_name(id), ty).unwrap();
+        Some((text, field.kind, field.item))
+    }
+
+    /// Translates an expression in the scope of the given fields, reporting it if it can't be.
+    fn expr(
+        &mut self,
+        source: &Yaml,
+        fields: &[(&'a str, String, Kind)],
+        path: &str,
+        key: &str,
+        target: Target,
+    ) -> Option<String> {
+        let source = match source {
+            Yaml::String(source) => source.clone(),
+            Yaml::Integer(_) | Yaml::Boolean(_) => yaml_text(source),
+            _ => {
+                self.report(&format!("{}/{}", path, key), "expected an expression");
+                return None;
+            }
+        };
+        let scope = FieldScope { gen: self, fields };
+        let result = Expr::parse(&source).and_then(|expr| match target {
+            Target::Value => expr.to_rust(&scope),
+            Target::Len => expr.to_rust_usize(&scope),
+            Target::Skip => expr.negate().to_rust(&scope),
+        });
+        match result {
+            Ok(text) => Some(text),
+            Err(message) => {
+                self.report(&format!("{}/{}", path, key), message);
+                None
+            }
+        }
+    }
+
+    /// Translates the `size` key. The outer `Option` is `None` if there is no `size` key, and the
+    /// inner one is `None` if it can't be expressed.
+    fn size(
+        &mut self,
+        attr: &Yaml,
+        path: &str,
+        fields: &[(&'a str, String, Kind)],
+        kind: &str,
+    ) -> Option<Option<String>> {
+        if attr["size-eos"].as_bool() == Some(true) {
+            self.report(
+                &format!("{}/size-eos", path),
+                format!("`size-eos` is not supported with `{}`", kind),
+            );
+            return Some(None);
+        }
+        match &attr["size"] {
+            Yaml::BadValue => None,
+            size => Some(self.expr(size, fields, path, "size", Target::Len)),
+        }
+    }
+
+    fn contents(
+        &mut self,
+        container: &str,
+        id: &str,
+        attr: &Yaml,
+        path: &str,
+    ) -> Option<FieldType> {
+        let mut bytes = Vec::new();
+        let parts = match &attr["contents"] {
+            Yaml::Array(parts) => parts.iter().collect(),
+            contents => vec![contents],
+        };
+        for part in parts {
+            match part {
+                Yaml::Integer(byte) if (0..=255).contains(byte) => bytes.push(*byte as u8),
+                Yaml::String(text) => bytes.extend(text.as_bytes()),
+                _ => {
+                    self.report(&format!("{}/contents", path), "expected bytes or strings");
+                    return None;
+                }
+            }
+        }
+        if !attr["type"].is_badvalue() || !attr["size"].is_badvalue() {
+            self.report(path, "`contents` can't be combined with `type` or `size`");
+            return None;
+        }
+
+        let ty = format!("{}{}", container, type_name(id));
+        let mut item = String::new();
+        writeln!(item, "{}::magic_bytes! {{", self.crate_path).unwrap();
+        writeln!(item, "    #[derive(Debug, Clone, Copy, PartialEq, Eq)]").unwrap();
+        writeln!(item, "    pub {}(b\"{}\");", ty, byte_string(&bytes)).unwrap();
+        writeln!(item, "}}").unwrap();
+        Some(FieldType {
+            ty,
+            ctx: ElemCtx::None,
+            kind: Kind::Other,
+            item: Some(item),
+        })
+    }
+
+    fn bytes(
+        &mut self,
+        attr: &Yaml,
+        path: &str,
+        fields: &[(&'a str, String, Kind)],
+    ) -> Option<FieldType> {
+        let ctx = if attr["size-eos"].as_bool() == Some(true) {
+            format!("{}::ctx::ToEnd", self.crate_path)
+        } else {
+            match self.size(attr, path, fields, "raw bytes") {
+                Some(size) => format!("{}::ctx::Len({})", self.crate_path, size?),
+                None => {
+                    self.report(path, "fields without a type need `size` or `size-eos`");
+                    return None;
+                }
+            }
+        };
+        Some(FieldType {
+            ty: "Vec<u8>".into(),
+            ctx: ElemCtx::Value(ctx),
+            kind: Kind::Sequence,
+            item: None,
+        })
+    }
+
+    fn string(
+        &mut self,
+        ty: &str,
+        attr: &Yaml,
+        path: &str,
+        fields: &[(&'a str, String, Kind)],
+    ) -> Option<FieldType> {
+        match attr["encoding"].as_str().or(self.encoding) {
+            Some(encoding)
+                if encoding.eq_ignore_ascii_case("utf-8")
+                    || encoding.eq_ignore_ascii_case("utf8")
+                    || encoding.eq_ignore_ascii_case("ascii") => {}
+            Some(encoding) => {
+                self.report(
+                    &format!("{}/encoding", path),
+                    format!(
+                        "only UTF-8 and ASCII strings are supported, not `{}`",
+                        encoding
+                    ),
+                );
+                return None;
+            }
+            None => {
+                self.report(path, "strings need an `encoding`");
+                return None;
+            }
+        }
+
+        let terminator = match (ty, &attr["terminator"]) {
+            ("strz", Yaml::BadValue) => Some(0),
+            (_, Yaml::Integer(byte)) if (0..=255).contains(byte) => Some(*byte),
+            (_, Yaml::BadValue) => None,
+            _ => {
+                self.report(&format!("{}/terminator", path), "expected a byte");
+                return None;
+            }
+        };
+        let ctx = match terminator {
+            Some(terminator) => {
+                if !attr["size"].is_badvalue() || !attr["size-eos"].is_badvalue() {
+                    self.report(path, "terminated strings with a `size` are not supported");
+                    return None;
+                }
+                format!("{}::ctx::Until({})", self.crate_path, terminator)
+            }
+            None if attr["size-eos"].as_bool() == Some(true) => {
+                format!("{}::ctx::ToEnd", self.crate_path)
+            }
+            None => match self.size(attr, path, fields, "str") {
+                Some(size) => format!("{}::ctx::Len({})", self.crate_path, size?),
+                None => {
+                    self.report(path, "strings need a `size`, `size-eos` or `terminator`");
+                    return None;
+                }
+            },
+        };
+        Some(FieldType {
+            ty: format!("{}::util::Utf8", self.crate_path),
+            ctx: ElemCtx::Value(ctx),
+            kind: Kind::Str,
+            item: None,
+        })
+    }
+
+    /// Maps a primitive, enum or user type name.
+    fn named_type(&mut self, ty: &str, attr: &Yaml, path: &str) -> Option<FieldType> {
+        if let Some(prim) = self.primitive(ty, path) {
+            let prim = prim?;
+            let ctx = self.primitive_ctx(prim, path)?;
+            return match attr["enum"].as_str() {
+                Some(name) => {
+                    let rust_name = self.use_enum(name, prim, &format!("{}/enum", path))?;
+                    let ctx = match ctx {
+                        ElemCtx::Endian { explicit, .. } => ElemCtx::Endian {
+                            explicit,
+                            automatic: false,
+                        },
+                        ctx => ctx,
+                    };
+                    Some(FieldType {
+                        ty: rust_name,
+                        ctx,
+                        kind: Kind::Int,
+                        item: None,
+                    })
+                }
+                None => Some(FieldType {
+                    ty: prim.rust.into(),
+                    ctx,
+                    kind: if prim.is_int { Kind::Int } else { Kind::Other },
+                    item: None,
+                }),
+            };
+        }
+        if !attr["enum"].is_badvalue() {
+            self.report(
+                &format!("{}/enum", path),
+                "enums are only supported on integers",
+            );
+            return None;
+        }
+        if ty.contains('(') {
+            self.report(
+                &format!("{}/type", path),
+                "type parameters are not supported",
+            );
+            return None;
+        }
+        match self.type_index.get(ty) {
+            Some(&index) => Some(FieldType {
+                ty: self.types[index].rust_name.clone(),
+                ctx: ElemCtx::None,
+                kind: Kind::User(ty.to_owned()),
+                item: None,
+            }),
+            None => {
+                self.report(&format!("{}/type", path), format!("unknown type `{}`", ty));
+                None
+            }
+        }
+    }
+
+    /// Parses a primitive type name. The outer `Option` is `None` if the name is not a primitive,
+    /// and the inner one is `None` if it is an unsupported one.
+    fn primitive(&mut self, ty: &str, path: &str) -> Option<Option<Primitive>> {
+        let (base, endian) = match ty.len() {
+            4 if ty.ends_with("le") => (&ty[..2], Some("little")),
+            4 if ty.ends_with("be") => (&ty[..2], Some("big")),
+            _ => (ty, None),
+        };
+        let (rust, size, is_int) = match base {
+            "u1" => ("u8", 1, true),
+            "u2" => ("u16", 2, true),
+            "u4" => ("u32", 4, true),
+            "u8" => ("u64", 8, true),
+            "s1" => ("i8", 1, true),
+            "s2" => ("i16", 2, true),
+            "s4" => ("i32", 4, true),
+            "s8" => ("i64", 8, true),
+            "f4" => ("f32", 4, false),
+            "f8" => ("f64", 8, false),
+            _ if is_bit_type(ty) => {
+                self.report(
+                    &format!("{}/type", path),
+                    "bit-sized integers are not supported",
+                );
+                return Some(None);
+            }
+            _ => return None,
+        };
+        Some(Some(Primitive {
+            rust,
+            size,
+            is_int,
+            endian,
+        }))
+    }
+
+    fn primitive_ctx(&mut self, prim: Primitive, path: &str) -> Option<ElemCtx> {
+        if prim.size == 1 {
+            return Some(ElemCtx::None);
+        }
+        if prim.endian.is_none() && self.endian.is_none() {
+            self.report(
+                &format!("{}/type", path),
+                "multi-byte types need an explicit endianness or `meta/endian`",
+            );
+            return None;
+        }
+        Some(ElemCtx::Endian {
+            explicit: prim.endian,
+            automatic: true,
+        })
+    }
+
+    /// Records the integer type of an enum, which must be the same for all of its uses.
+    fn use_enum(&mut self, name: &str, prim: Primitive, path: &str) -> Option<String> {
+        let index = match self.enum_index.get(name) {
+            Some(&index) => index,
+            None => {
+                self.report(path, format!("unknown enum `{}`", name));
+                return None;
+            }
+        };
+        let prim = Primitive {
+            endian: None,
+            ..prim
+        };
+        match self.enums[index].prim {
+            None => self.enums[index].prim = Some(prim),
+            Some(existing) if existing == prim => {}
+            Some(existing) => {
+                self.report(
+                    path,
+                    format!(
+                        "enum `{}` is already used with `{}`, not `{}`",
+                        name, existing.rust, prim.rust
+                    ),
+                );
+                return None;
+            }
+        }
+        Some(self.enums[index].rust_name.clone())
+    }
+
+    /// Generates an enum for a `switch-on` type, which takes the switch value as context.
+    fn switch(
+        &mut self,
+        container: &str,
+        id: &str,
+        attr: &'a Yaml,
+        path: &str,
+        fields: &[(&'a str, String, Kind)],
+    ) -> Option<FieldType> {
+        let type_path = format!("{}/type", path);
+        let switch_on = self.expr(
+            &attr["type"]["switch-on"],
+            fields,
+            &type_path,
+            "switch-on",
+            Target::Value,
+        )?;
+        let cases = match attr["type"]["cases"].as_hash() {
+            Some(cases) => cases,
+            None => {
+                self.report(&format!("{}/cases", type_path), "expected a mapping");
+                return None;
+            }
+        };
+
+        let rust_name = format!("{}{}", container, type_name(id));
+        let mut variants = String::new();
+        let mut names: Vec<String> = Vec::new();
+        for (case, ty) in cases {
+            let case_path = format!("{}/cases/{}", type_path, yaml_text(case));
+            let case_id = match case {
+                Yaml::Integer(value) => value.to_string(),
+                Yaml::String(value) if value == "_" => {
+                    self.report(&case_path, "default cases are not supported");
+                    continue;
+                }
+                Yaml::String(value) => {
+                    let scope = FieldScope { gen: self, fields };
+                    match Expr::parse(value).and_then(|expr| match expr {
+                        Expr::EnumValue(..) => expr.to_rust(&scope),
+                        _ => Err("only integer and enum cases are supported".into()),
+                    }) {
+                        Ok(id) => id,
+                        Err(message) => {
+                            self.report(&case_path, message);
+                            continue;
+                        }
+                    }
+                }
+                _ => {
+                    self.report(&case_path, "only integer and enum cases are supported");
+                    continue;
+                }
+            };
+            let ty = match ty.as_str() {
+                Some(ty) => ty,
+                None => {
+                    self.report(&case_path, "expected a type name");
+                    continue;
+                }
+            };
+            let field = match self.named_type(ty, &Yaml::BadValue, &case_path) {
+                Some(field) => field,
+                None => continue,
+            };
+
+            let mut name = type_name(ty);
+            if names.contains(&name) {
+                name = format!("{}{}", name, names.len());
+            }
+            names.push(name.clone());
+            let field_attr = match field.ctx {
+                ElemCtx::Endian {
+                    explicit: Some(endian),
+                    ..
+                } => format!("#[declio(endian = \"{}\")] ", endian),
+                ElemCtx::Endian {
+                    explicit: None,
+                    automatic: false,
+                } => "#[declio(endian)] ".into(),
+                _ => String::new(),
+            };
+            writeln!(variants, "    #[declio(id = \"{}\")]", case_id).unwrap();
+            writeln!(variants, "    {}({}{}),", name, field_attr, field.ty).unwrap();
+        }
+
+        let mut item = String::new();
+        writeln!(
+            item,
+            "#[derive(Debug, Clone, PartialEq, {0}::Encode, {0}::Decode)]",
+            self.crate_path
+        )
+        .unwrap();
+        let mut attrs = self.crate_attr();
+        attrs.push("ctx = \"id: i64\"".into());
+        attrs.push("id_expr = \"id\"".into());
+        if let Some(endian) = self.endian {
+            attrs.push(format!("endian = \"{}\"", endian));
+        }
+        write_attrs(&mut item, "", &attrs);
+        writeln!(item, "pub enum {} {{\n{}}}", rust_name, variants).unwrap();
+        Some(FieldType {
+            ty: rust_name,
+            ctx: ElemCtx::Value(switch_on),
+            kind: Kind::Other,
+            item: Some(item),
+        })
+    }
+
+    fn enum_item(&mut self, index: usize) {
+        let path = self.enums[index].path.clone();
+        let prim = match self.enums[index].prim {
+            Some(prim) => prim,
+            None => {
+                self.report(
+                    &path,
+                    "enums that are not used by any field have no integer type",
+                );
+                return;
+            }
+        };
+        let values = match self.enums[index].values.as_hash() {
+            Some(values) => values,
+            None => {
+                self.report(&path, "expected a mapping");
+                return;
+            }
+        };
+
+        let mut variants = String::new();
+        for (value, name) in values {
+            let value_path = format!("{}/{}", path, yaml_text(value));
+            let value = match value {
+                Yaml::Integer(value) => *value,
+                _ => {
+                    self.report(&value_path, "only integer values are supported");
+                    continue;
+                }
+            };
+            let (name, doc) = match name {
+                Yaml::String(name) => (name.as_str(), &Yaml::BadValue),
+                Yaml::Hash(_) => match name["id"].as_str() {
+                    Some(id) => (id, &name["doc"]),
+                    None => {
+                        self.report(&value_path, "expected an `id`");
+                        continue;
+                    }
+                },
+                _ => {
+                    self.report(&value_path, "expected a name");
+                    continue;
+                }
+            };
+            write_doc(&mut variants, "    ", doc);
+            writeln!(variants, "    #[declio(id = \"{}\")]", value).unwrap();
+            writeln!(variants, "    {} = {},", type_name(name), value).unwrap();
+        }
+
+        let mut item = String::new();
+        writeln!(
+            item,
+            "#[derive(Debug, Clone, Copy, PartialEq, Eq, {0}::Encode, {0}::Decode)]",
+            self.crate_path
+        )
+        .unwrap();
+        let mut attrs = self.crate_attr();
+        attrs.push(format!("id_type = \"{}\"", prim.rust));
+        if prim.size > 1 {
+            attrs.push("endian = \"ctx\"".into());
+        }
+        write_attrs(&mut item, "", &attrs);
+        writeln!(item, "#[repr({})]", prim.rust).unwrap();
+        writeln!(
+            item,
+            "pub enum {} {{\n{}}}",
+            self.enums[index].rust_name, variants
+        )
+        .unwrap();
+        self.items.push(item);
+    }
+
+    fn crate_attr(&self) -> Vec<String> {
+        if self.crate_path == "declio" {
+            Vec::new()
+        } else {
+            vec![format!("crate_path = \"{}\"", self.crate_path)]
+        }
+    }
+
+    fn endian_value(&self, explicit: Option<&str>) -> String {
+        let endian = match explicit.or(self.endian) {
+            Some("little") => "Little",
+            _ => "Big",
+        };
+        format!("{}::ctx::Endian::{}", self.crate_path, endian)
+    }
+
+    /// Returns the kind of a field of a user-defined type, without generating it.
+    fn member_kind(&self, attr: &Yaml) -> Kind {
+        let is_supported = attr.as_hash().is_some_and(|attr| {
+            attr.keys()
+                .filter_map(Yaml::as_str)
+                .all(|key| FIELD_KEYS.contains(&key) || LOSSY_FIELD_KEYS.contains(&key))
+        });
+        if !is_supported || !attr["if"].is_badvalue() || !attr["contents"].is_badvalue() {
+            return Kind::Other;
+        }
+        if !attr["repeat"].is_badvalue() {
+            return Kind::Sequence;
+        }
+        match &attr["type"] {
+            Yaml::BadValue => Kind::Sequence,
+            Yaml::String(ty) if ty == "str" || ty == "strz" => Kind::Str,
+            Yaml::String(ty) if self.type_index.contains_key(ty.as_str()) => Kind::User(ty.clone()),
+            Yaml::String(ty) if ty.starts_with('u') || ty.starts_with('s') => {
+                if is_bit_type(ty) {
+                    Kind::Other
+                } else {
+                    Kind::Int
+                }
+            }
+            _ => Kind::Other,
+        }
+    }
+}
+
+impl Scope for FieldScope<'_, '_> {
+    fn field(&self, name: &str) -> Option<(String, Kind)> {
+        self.fields
+            .iter()
+            .find(|(id, _, _)| *id == name)
+            .map(|(_, ident, kind)| (ident.clone(), kind.clone()))
+    }
+
+    fn member(&self, ty: &str, name: &str) -> Option<(String, Kind)> {
+        let spec = self.gen.types[*self.gen.type_index.get(ty)?].spec;
+        let attr = spec["seq"]
+            .as_vec()?
+            .iter()
+            .find(|attr| attr["id"].as_str() == Some(name))?;
+        Some((field_name(name), self.gen.member_kind(attr)))
+    }
+
+    fn enum_value(&self, name: &str, value: &str) -> Option<String> {
+        let def = &self.gen.enums[*self.gen.enum_index.get(name)?];
+        let exists = def.values.as_hash()?.values().any(|v| match v {
+            Yaml::String(v) => v == value,
+            v => v["id"].as_str() == Some(value),
+        });
+        if exists {
+            Some(format!("{}::{}", def.rust_name, type_name(value)))
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns whether `value` can be represented by the Rust integer type `rust`.
+fn int_fits(rust: &str, value: i128) -> bool {
+    match rust {
+        "u8" => u8::try_from(value).is_ok(),
+        "u16" => u16::try_from(value).is_ok(),
+        "u32" => u32::try_from(value).is_ok(),
+        "u64" => u64::try_from(value).is_ok(),
+        "i8" => i8::try_from(value).is_ok(),
+        "i16" => i16::try_from(value).is_ok(),
+        "i32" => i32::try_from(value).is_ok(),
+        "i64" => i64::try_from(value).is_ok(),
+        _ => false,
+    }
+}
+
+fn is_bit_type(ty: &str) -> bool {
+    let digits = ty.strip_prefix('b').unwrap_or_default();
+    let digits = digits
+        .strip_suffix("le")
+        .or_else(|| digits.strip_suffix("be"))
+        .unwrap_or(digits);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Converts a `snake_case` Kaitai identifier into an `UpperCamelCase` Rust type name.
+fn type_name(id: &str) -> String {
+    id.split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Converts a Kaitai identifier into a Rust field name, escaping keywords.
+fn field_name(id: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do",
+        "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let",
+        "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+        "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+        "virtual", "where", "while", "yield",
+    ];
+    match id {
+        "self" | "super" | "crate" => format!("{}_", id),
+        _ if KEYWORDS.contains(&id) => format!("r#{}", id),
+        _ => id.to_owned(),
+    }
+}
+
+fn byte_string(bytes: &[u8]) -> String {
+    let mut text = String::new();
+    for &byte in bytes {
+        match byte {
+            b'"' | b'\\' => write!(text, "\\{}", byte as char).unwrap(),
+            0x20..=0x7e => text.push(byte as char),
+            _ => write!(text, "\\x{:02x}", byte).unwrap(),
+        }
+    }
+    text
+}
+
+fn yaml_text(yaml: &Yaml) -> String {
+    match yaml {
+        Yaml::Integer(value) => value.to_string(),
+        Yaml::String(value) => value.clone(),
+        Yaml::Boolean(value) => value.to_string(),
+        _ => "?".into(),
+    }
+}
+
+fn write_doc(out: &mut String, indent: &str, doc: &Yaml) {
+    if let Some(doc) = doc.as_str() {
+        for line in doc.trim_end().lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                writeln!(out, "{}///", indent).unwrap();
+            } else {
+                writeln!(out, "{}/// {}", indent, line).unwrap();
+            }
+        }
+    }
+}
+
+fn write_attrs(out: &mut String, indent: &str, attrs: &[String]) {
+    if !attrs.is_empty() {
+        writeln!(out, "{}#[declio({})]", indent, attrs.join(", ")).unwrap();
+    }
+}
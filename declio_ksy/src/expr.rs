@@ -0,0 +1,449 @@
+//! Translation of Kaitai Struct expressions into Rust expressions.
+//!
+//! Only a subset of the expression language is supported: integer and boolean literals, enum
+//! values, references to earlier fields (and their members), and the usual arithmetic, bitwise,
+//! comparison and logical operators. Integer values are widened to `i64`, so that fields of
+//! different types can be mixed freely.
+
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// What an expression can do with a field, based on its type.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Kind {
+    /// An integer, or an enum which can be cast to one.
+    Int,
+    /// A byte buffer or an array, which has a `size` or `length`.
+    Sequence,
+    /// A string, which has a `length`.
+    Str,
+    /// A user-defined type, with members of its own.
+    User(String),
+    /// Anything that can't be used in an expression.
+    Other,
+}
+
+/// Resolves the names used in an expression.
+pub(crate) trait Scope {
+    /// Looks up an earlier field of the current type, returning its Rust name and kind.
+    fn field(&self, name: &str) -> Option<(String, Kind)>;
+
+    /// Looks up a field of a user-defined type, returning its Rust name and kind.
+    fn member(&self, ty: &str, name: &str) -> Option<(String, Kind)>;
+
+    /// Looks up an enum value, returning its Rust path.
+    fn enum_value(&self, name: &str, value: &str) -> Option<String>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Int(i128),
+    Bool(bool),
+    Name(String),
+    EnumValue(String, String),
+    Member(Box<Expr>, String),
+    Unary(&'static str, Box<Expr>),
+    Binary(Box<Expr>, &'static str, Box<Expr>),
+    Ternary(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub(crate) fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.ternary()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected `{}` in expression", token)),
+        }
+    }
+
+    /// Returns the logical negation of a condition, pushing it into comparisons where possible.
+    pub(crate) fn negate(self) -> Self {
+        match self {
+            Expr::Bool(value) => Expr::Bool(!value),
+            Expr::Unary("!", operand) => *operand,
+            Expr::Binary(lhs, "&&", rhs) => {
+                Expr::Binary(Box::new(lhs.negate()), "||", Box::new(rhs.negate()))
+            }
+            Expr::Binary(lhs, "||", rhs) => {
+                Expr::Binary(Box::new(lhs.negate()), "&&", Box::new(rhs.negate()))
+            }
+            Expr::Binary(lhs, op, rhs) => match op {
+                "==" => Expr::Binary(lhs, "!=", rhs),
+                "!=" => Expr::Binary(lhs, "==", rhs),
+                "<" => Expr::Binary(lhs, ">=", rhs),
+                "<=" => Expr::Binary(lhs, ">", rhs),
+                ">" => Expr::Binary(lhs, "<=", rhs),
+                ">=" => Expr::Binary(lhs, "<", rhs),
+                _ => Expr::Unary("!", Box::new(Expr::Binary(lhs, op, rhs))),
+            },
+            expr => Expr::Unary("!", Box::new(expr)),
+        }
+    }
+
+    /// Translates the expression into an `i64` or `bool` valued Rust expression.
+    pub(crate) fn to_rust(&self, scope: &dyn Scope) -> Result<String, String> {
+        self.emit(scope).map(|(text, _)| text)
+    }
+
+    /// Translates the expression into a `usize` valued Rust expression, as used for lengths.
+    pub(crate) fn to_rust_usize(&self, scope: &dyn Scope) -> Result<String, String> {
+        match self {
+            Expr::Int(value) if *value >= 0 => Ok(value.to_string()),
+            Expr::Name(name) => {
+                let (ident, kind) = resolve(scope, name)?;
+                match kind {
+                    Kind::Int => Ok(format!("*{} as usize", ident)),
+                    _ => Err(format!("`{}` is not an integer", name)),
+                }
+            }
+            _ => {
+                let (text, needs_parens) = self.emit(scope)?;
+                Ok(format!("{} as usize", parens(text, needs_parens)))
+            }
+        }
+    }
+
+    /// Returns the translated text, and whether it needs parentheses when used as an operand.
+    fn emit(&self, scope: &dyn Scope) -> Result<(String, bool), String> {
+        match self {
+            Expr::Int(value) => {
+                if *value > i64::MAX as i128 {
+                    return Err(format!("integer literal {} is out of range", value));
+                }
+                Ok((value.to_string(), false))
+            }
+            Expr::Bool(value) => Ok((value.to_string(), false)),
+            Expr::EnumValue(name, value) => match scope.enum_value(name, value) {
+                Some(path) => Ok((format!("{} as i64", path), true)),
+                None => Err(format!("unknown enum value `{}::{}`", name, value)),
+            },
+            Expr::Name(_) | Expr::Member(..) => self.emit_place(scope),
+            Expr::Unary(op, operand) => {
+                let (text, needs_parens) = operand.emit(scope)?;
+                Ok((format!("{}{}", op, parens(text, needs_parens)), false))
+            }
+            Expr::Binary(lhs, op, rhs) => {
+                let (lhs, lhs_parens) = lhs.emit(scope)?;
+                let (rhs, rhs_parens) = rhs.emit(scope)?;
+                let text = format!(
+                    "{} {} {}",
+                    parens(lhs, lhs_parens),
+                    op,
+                    parens(rhs, rhs_parens)
+                );
+                Ok((text, true))
+            }
+            Expr::Ternary(cond, then, otherwise) => {
+                let text = format!(
+                    "if {} {{ {} }} else {{ {} }}",
+                    cond.to_rust(scope)?,
+                    then.to_rust(scope)?,
+                    otherwise.to_rust(scope)?,
+                );
+                Ok((text, true))
+            }
+        }
+    }
+
+    /// Translates a field reference, possibly followed by member accesses.
+    fn emit_place(&self, scope: &dyn Scope) -> Result<(String, bool), String> {
+        let (path, kind, direct) = self.place(scope)?;
+        match kind {
+            Kind::Int if direct => Ok((format!("*{} as i64", path), true)),
+            Kind::Int => Ok((format!("{} as i64", path), true)),
+            _ => Err(format!("`{}` is not an integer", self.source_name())),
+        }
+    }
+
+    /// Resolves a chain of member accesses into a Rust place, its kind, and whether it is a field
+    /// of the current type (which is bound by reference).
+    fn place(&self, scope: &dyn Scope) -> Result<(String, Kind, bool), String> {
+        match self {
+            Expr::Name(name) => {
+                let (ident, kind) = resolve(scope, name)?;
+                Ok((ident, kind, true))
+            }
+            Expr::Member(base, member) => {
+                let (path, kind, _) = base.place(scope)?;
+                match (kind, member.as_str()) {
+                    (Kind::Sequence, "size") | (Kind::Sequence, "length") => {
+                        Ok((format!("{}.len()", path), Kind::Int, false))
+                    }
+                    (Kind::Str, "length") => {
+                        Ok((format!("{}.0.chars().count()", path), Kind::Int, false))
+                    }
+                    (Kind::User(ty), _) => match scope.member(&ty, member) {
+                        Some((ident, kind)) => Ok((format!("{}.{}", path, ident), kind, false)),
+                        None => Err(format!("unknown member `{}` of type `{}`", member, ty)),
+                    },
+                    _ => Err(format!(
+                        "unsupported member `{}` of `{}`",
+                        member,
+                        base.source_name()
+                    )),
+                }
+            }
+            _ => Err("unsupported member access on an expression".into()),
+        }
+    }
+
+    fn source_name(&self) -> String {
+        match self {
+            Expr::Name(name) => name.clone(),
+            Expr::Member(base, member) => format!("{}.{}", base.source_name(), member),
+            _ => "expression".into(),
+        }
+    }
+}
+
+fn resolve(scope: &dyn Scope, name: &str) -> Result<(String, Kind), String> {
+    if name.starts_with('_') {
+        return Err(format!("`{}` is not supported", name));
+    }
+    scope
+        .field(name)
+        .ok_or_else(|| format!("unknown field `{}`", name))
+}
+
+fn parens(text: String, needs_parens: bool) -> String {
+    if needs_parens {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Int(i128),
+    Ident(String),
+    Op(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Int(value) => write!(f, "{}", value),
+            Token::Ident(ident) => write!(f, "{}", ident),
+            Token::Op(op) => write!(f, "{}", op),
+        }
+    }
+}
+
+/// Operators, with longer ones first so they take precedence over their prefixes.
+const OPS: &[&str] = &[
+    "::", "<=", ">=", "==", "!=", "<<", ">>", "+", "-", "*", "/", "%", "<", ">", "&", "|", "^",
+    "~", "(", ")", "?", ":", ".",
+];
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            tokens.push(Token::Int(int_literal(&mut chars)?));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+            {
+                ident.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Ident(ident));
+        } else {
+            let rest: String = chars.clone().take(2).collect();
+            let op = OPS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| format!("unsupported character `{}` in expression", c))?;
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            tokens.push(Token::Op(op));
+        }
+    }
+    Ok(tokens)
+}
+
+fn int_literal(chars: &mut Peekable<Chars>) -> Result<i128, String> {
+    let mut text = String::new();
+    while let Some(&c) = chars
+        .peek()
+        .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+    {
+        if c != '_' {
+            text.push(c);
+        }
+        chars.next();
+    }
+    if chars.peek() == Some(&'.') && chars.clone().nth(1).is_some_and(|c| c.is_ascii_digit()) {
+        return Err("floating-point literals are not supported".into());
+    }
+    let (digits, radix) = match text.get(..2) {
+        Some("0x") | Some("0X") => (&text[2..], 16),
+        Some("0b") | Some("0B") => (&text[2..], 2),
+        Some("0o") | Some("0O") => (&text[2..], 8),
+        _ => (&text[..], 10),
+    };
+    i128::from_str_radix(digits, radix).map_err(|_| format!("invalid integer literal `{}`", text))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+/// Binary operators by precedence level, from lowest to highest, with their Rust equivalents.
+const BINARY_OPS: &[&[(&str, &str)]] = &[
+    &[("or", "||")],
+    &[("and", "&&")],
+    &[
+        ("==", "=="),
+        ("!=", "!="),
+        ("<", "<"),
+        ("<=", "<="),
+        (">", ">"),
+        (">=", ">="),
+    ],
+    &[("|", "|")],
+    &[("^", "^")],
+    &[("&", "&")],
+    &[("<<", "<<"), (">>", ">>")],
+    &[("+", "+"), ("-", "-")],
+    &[("*", "*"), ("/", "/"), ("%", "%")],
+];
+
+/// The precedence level of `not`, which sits between `and` and the comparisons.
+const NOT_LEVEL: usize = 2;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        let matches = match self.peek() {
+            Some(Token::Op(op)) => *op == text,
+            Some(Token::Ident(ident)) => ident == text,
+            _ => false,
+        };
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn expect(&mut self, text: &str) -> Result<(), String> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(format!("expected `{}` in expression", text))
+        }
+    }
+
+    fn ternary(&mut self) -> Result<Expr, String> {
+        let cond = self.binary(0)?;
+        if self.eat("?") {
+            let then = self.ternary()?;
+            self.expect(":")?;
+            let otherwise = self.ternary()?;
+            Ok(Expr::Ternary(
+                Box::new(cond),
+                Box::new(then),
+                Box::new(otherwise),
+            ))
+        } else {
+            Ok(cond)
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == NOT_LEVEL && self.eat("not") {
+            let operand = self.binary(level)?;
+            return Ok(Expr::Unary("!", Box::new(operand)));
+        }
+        let ops = match BINARY_OPS.get(level) {
+            Some(ops) => ops,
+            None => return self.unary(),
+        };
+        let mut lhs = self.binary(level + 1)?;
+        'outer: loop {
+            for (op, rust_op) in ops.iter() {
+                if self.eat(op) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Expr::Binary(Box::new(lhs), rust_op, Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            Ok(Expr::Unary("-", Box::new(self.unary()?)))
+        } else if self.eat("~") {
+            Ok(Expr::Unary("!", Box::new(self.unary()?)))
+        } else {
+            self.postfix()
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr, String> {
+        let mut expr = self.primary()?;
+        while self.eat(".") {
+            match self.peek().cloned() {
+                Some(Token::Ident(member)) => {
+                    self.pos += 1;
+                    if self.peek() == Some(&Token::Op("(")) {
+                        return Err(format!("method `{}` is not supported", member));
+                    }
+                    expr = Expr::Member(Box::new(expr), member);
+                }
+                _ => return Err("expected member name after `.`".into()),
+            }
+        }
+        if let Some(Token::Op("(")) = self.peek() {
+            return Err("function calls are not supported".into());
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| String::from("unexpected end of expression"))?;
+        self.pos += 1;
+        match token {
+            Token::Int(value) => Ok(Expr::Int(value)),
+            Token::Ident(ident) if ident == "true" => Ok(Expr::Bool(true)),
+            Token::Ident(ident) if ident == "false" => Ok(Expr::Bool(false)),
+            Token::Ident(ident) => {
+                if self.eat("::") {
+                    match self.peek().cloned() {
+                        Some(Token::Ident(value)) => {
+                            self.pos += 1;
+                            Ok(Expr::EnumValue(ident, value))
+                        }
+                        _ => Err("expected enum value after `::`".into()),
+                    }
+                } else {
+                    Ok(Expr::Name(ident))
+                }
+            }
+            Token::Op("(") => {
+                let expr = self.ternary()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            token => Err(format!("unexpected `{}` in expression", token)),
+        }
+    }
+}
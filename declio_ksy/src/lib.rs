@@ -0,0 +1,208 @@
+//! Generates `declio` types from [Kaitai Struct] specifications.
+//!
+//! Each type in a `.ksy` file becomes a struct that derives `Encode` and `Decode`, with its
+//! sequence of attributes translated into fields and `#[declio(...)]` attributes. Enums become
+//! Rust enums with an `id_type`, `contents` become [`magic_bytes!`] types, and `switch-on` types
+//! become enums that take the switch value as context.
+//!
+//! The generator is meant to be run from a build script, with the output included in the crate:
+//!
+//! ```no_run
+//! // In the `main` function of build.rs:
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! declio_ksy::Builder::new()
+//!     .compile("formats/packet.ksy", format!("{}/packet.rs", out_dir))
+//!     .unwrap();
+//! ```
+//!
+//! ```ignore
+//! // src/lib.rs
+//! include!(concat!(env!("OUT_DIR"), "/packet.rs"));
+//! ```
+//!
+//! # Supported constructs
+//!
+//! Kaitai Struct is much more expressive than the `declio` attributes, so only a subset of it can
+//! be translated. The generator supports:
+//!
+//! - Integer and floating-point types, with `meta/endian` or an explicit endianness.
+//! - User types, including nested ones, and their `size`.
+//! - Byte arrays and UTF-8 or ASCII strings with a `size`, `size-eos` or `terminator`.
+//! - `contents`, `enum`, `if`, and `switch-on` types with integer or enum cases.
+//! - `repeat: expr`, `repeat: eos`, and `repeat: until` with a `_ == <integer>` condition on
+//!   integers (but not enums).
+//! - Expressions made of literals, enum values, earlier fields and their members, `size` and
+//!   `length`, and the arithmetic, bitwise, comparison and logical operators.
+//!
+//! Everything else, like instances, parameters, bit-sized integers, `process` and `_io`, is
+//! reported as [`Unsupported`] along with its location in the specification. Unsupported fields
+//! are left out of the generated types, so [`Builder::compile`] fails on them unless
+//! [`Builder::allow_unsupported`] is set.
+//!
+//! Note that unlike Kaitai Struct enums, the generated enums fail to decode unknown values. And
+//! unlike Kaitai Struct, `repeat: until` arrays do not include their terminating element, which is
+//! always equal to the integer in the condition; the generated field's documentation says so.
+//!
+//! [Kaitai Struct]: https://kaitai.io/
+//! [`magic_bytes!`]: https://docs.rs/declio/*/declio/macro.magic_bytes.html
+
+mod codegen;
+mod expr;
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Configures and runs the generator.
+#[derive(Debug, Clone)]
+pub struct Builder {
+    crate_path: String,
+    allow_unsupported: bool,
+}
+
+impl Builder {
+    /// Creates a builder with the default options.
+    pub fn new() -> Self {
+        Self {
+            crate_path: "declio".into(),
+            allow_unsupported: false,
+        }
+    }
+
+    /// Sets the path to the `declio` crate used by the generated code, which is `declio` by
+    /// default. It is also passed to the derive macros as their `crate_path`.
+    pub fn crate_path(mut self, crate_path: impl Into<String>) -> Self {
+        self.crate_path = crate_path.into();
+        self
+    }
+
+    /// Whether [`compile`](Self::compile) should succeed even if the specification has
+    /// unsupported constructs, which are then printed as build warnings. Disabled by default.
+    pub fn allow_unsupported(mut self, allow_unsupported: bool) -> Self {
+        self.allow_unsupported = allow_unsupported;
+        self
+    }
+
+    /// Generates code from the source of a `.ksy` file.
+    ///
+    /// Unsupported constructs are returned with the code, regardless of
+    /// [`allow_unsupported`](Self::allow_unsupported).
+    pub fn generate(&self, source: &str) -> Result<Generated, Error> {
+        let (code, unsupported) = codegen::generate(source, &self.crate_path)?;
+        Ok(Generated { code, unsupported })
+    }
+
+    /// Generates code from the `.ksy` file at `input`, and writes it to `output`.
+    ///
+    /// This is intended to be called from a build script: it tells Cargo to rerun the script when
+    /// the input changes, and prints a warning for each unsupported construct if they are
+    /// allowed.
+    pub fn compile(
+        &self,
+        input: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+    ) -> Result<Generated, Error> {
+        let input = input.as_ref();
+        println!("cargo:rerun-if-changed={}", input.display());
+
+        let generated = self.generate(&fs::read_to_string(input)?)?;
+        if !generated.unsupported.is_empty() {
+            if !self.allow_unsupported {
+                return Err(Error::Unsupported(generated.unsupported));
+            }
+            for unsupported in &generated.unsupported {
+                println!("cargo:warning={}: {}", input.display(), unsupported);
+            }
+        }
+        fs::write(output, &generated.code)?;
+        Ok(generated)
+    }
+}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Generates code from the source of a `.ksy` file, with the default options.
+pub fn generate(source: &str) -> Result<Generated, Error> {
+    Builder::new().generate(source)
+}
+
+/// The output of the generator.
+#[derive(Debug, Clone)]
+pub struct Generated {
+    /// The generated Rust source code.
+    pub code: String,
+    /// The constructs that could not be translated, in the order they appear.
+    pub unsupported: Vec<Unsupported>,
+}
+
+/// A construct in a specification that can't be expressed with `declio`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Unsupported {
+    /// The location of the construct, as a path of YAML keys like `/types/header/seq/2/process`.
+    pub path: String,
+    /// Describes what is not supported.
+    pub message: String,
+}
+
+impl fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Errors that prevent code from being generated.
+#[derive(Debug)]
+pub enum Error {
+    /// The input file or the output file could not be accessed.
+    Io(io::Error),
+    /// The specification is not valid YAML.
+    Yaml(yaml_rust2::ScanError),
+    /// The specification is not a valid Kaitai Struct specification.
+    Invalid(String),
+    /// The specification has constructs that can't be expressed, and they are not allowed.
+    Unsupported(Vec<Unsupported>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "{}", error),
+            Error::Yaml(error) => write!(f, "{}", error),
+            Error::Invalid(message) => write!(f, "invalid specification: {}", message),
+            Error::Unsupported(unsupported) => {
+                write!(f, "unsupported constructs in specification:")?;
+                for unsupported in unsupported {
+                    write!(f, "\n  {}", unsupported)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Yaml(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<yaml_rust2::ScanError> for Error {
+    fn from(error: yaml_rust2::ScanError) -> Self {
+        Self::Yaml(error)
+    }
+}
@@ -0,0 +1,209 @@
+use declio_ksy::{Builder, Error, Unsupported};
+
+mod packet {
+    include!("ksy/packet.rs");
+}
+
+#[test]
+fn generated_code() {
+    let generated = declio_ksy::generate(include_str!("ksy/packet.ksy")).unwrap();
+    assert_eq!(generated.unsupported, []);
+    assert_eq!(generated.code, include_str!("ksy/packet.rs"));
+}
+
+#[test]
+fn generated_types() {
+    use packet::*;
+
+    let value = Packet {
+        magic: PacketMagic,
+        kind: Kind::Data,
+        len: 2,
+        checksum: 0xdeadbeef,
+        body: vec![0xaa, 0xbb],
+        count: 2,
+        entries: vec![Entry { key: 1, value: -2 }, Entry { key: 3, value: 4 }],
+        name: "hi".into(),
+        extra: Some(-1),
+        payload: PacketPayload::Header(Header {
+            version: 5,
+            r#type: 6,
+        }),
+        header: Some(Header {
+            version: 7,
+            r#type: 8,
+        }),
+        version: Header {
+            version: 1,
+            r#type: 0,
+        },
+        tail: vec![0xcc, 0xdd],
+        trailer: vec![0x1234],
+    };
+    let bytes = [
+        b'P', b'K', // magic
+        0x01, // kind
+        0x00, 0x02, // len
+        0xef, 0xbe, 0xad, 0xde, // checksum
+        0xaa, 0xbb, // body
+        0x02, // count
+        0x01, 0xfe, 0xff, 0x03, 0x04, 0x00, // entries
+        b'h', b'i', 0x00, // name
+        0xff, 0xff, // extra
+        0x05, 0x06, // payload
+        0x07, 0x08, // header
+        0x01, 0x00, // version
+        0xcc, 0xdd, // tail
+        0x12, 0x34, 0x00, 0x00, // trailer
+    ];
+
+    assert_eq!(declio::to_bytes(&value).unwrap(), bytes);
+    assert_eq!(declio::from_bytes::<Packet>(&bytes).unwrap(), value);
+
+    let mut ping = value;
+    ping.kind = Kind::Ping;
+    ping.extra = None;
+    ping.payload = PacketPayload::Ping(Ping);
+    let bytes = declio::to_bytes(&ping).unwrap();
+    assert_eq!(declio::from_bytes::<Packet>(&bytes).unwrap(), ping);
+}
+
+const UNSUPPORTED: &str = "
+meta:
+  id: unsupported
+  endian: le
+seq:
+  - id: flags
+    type: b4
+  - id: data
+    size: 4
+    process: xor(0x55)
+  - id: len
+    type: u2
+  - id: body
+    size: len
+    valid:
+      eq: 4
+  - id: rest
+    size: _io.size - _io.pos
+instances:
+  last:
+    pos: 0
+    type: u1
+";
+
+#[test]
+fn unsupported() {
+    let generated = declio_ksy::generate(UNSUPPORTED).unwrap();
+    let paths: Vec<&str> = generated
+        .unsupported
+        .iter()
+        .map(|unsupported| unsupported.path.as_str())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "/instances",
+            "/seq/0/type",
+            "/seq/1/process",
+            "/seq/3/valid",
+            "/seq/4/size"
+        ]
+    );
+    assert_eq!(
+        generated.unsupported[4],
+        Unsupported {
+            path: "/seq/4/size".into(),
+            message: "`_io` is not supported".into(),
+        }
+    );
+
+    // Unsupported fields are left out, and lossy ones are kept.
+    assert!(!generated.code.contains("pub flags"));
+    assert!(!generated.code.contains("pub data"));
+    assert!(generated.code.contains("pub body"));
+    assert!(!generated.code.contains("pub rest"));
+}
+
+#[test]
+fn unsupported_repeat_until() {
+    let generated = declio_ksy::generate(
+        "
+meta:
+  id: terminated
+  endian: le
+seq:
+  - id: kinds
+    type: u1
+    enum: kind
+    repeat: until
+    repeat-until: _ == 0
+  - id: words
+    type: u2
+    repeat: until
+    repeat-until: _ == 70000
+  - id: bytes
+    type: u1
+    repeat: until
+    repeat-until: _ == 255
+enums:
+  kind:
+    0: end
+    1: data
+",
+    )
+    .unwrap();
+    assert_eq!(
+        generated.unsupported,
+        [
+            Unsupported {
+                path: "/seq/0/repeat-until".into(),
+                message: "conditions on enums are not supported".into(),
+            },
+            Unsupported {
+                path: "/seq/1/repeat-until".into(),
+                message: "`70000` is out of range for `u16`".into(),
+            },
+        ]
+    );
+    assert!(!generated.code.contains("pub kinds"));
+    assert!(!generated.code.contains("pub words"));
+    assert!(generated.code.contains("Until(255)"));
+}
+
+#[test]
+fn compile() {
+    let dir = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"));
+    let input = dir.join("unsupported.ksy");
+    let output = dir.join("unsupported.rs");
+    std::fs::write(&input, UNSUPPORTED).unwrap();
+    let _ = std::fs::remove_file(&output);
+
+    match Builder::new().compile(&input, &output) {
+        Err(Error::Unsupported(unsupported)) => assert_eq!(unsupported.len(), 5),
+        result => panic!("expected unsupported error, got {:?}", result),
+    }
+    assert!(!output.exists());
+
+    let generated = Builder::new()
+        .allow_unsupported(true)
+        .crate_path("::declio")
+        .compile(&input, &output)
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), generated.code);
+    assert!(generated
+        .code
+        .contains("#[declio(crate_path = \"::declio\", endian = \"little\")]"));
+}
+
+#[test]
+fn invalid() {
+    assert!(matches!(
+        declio_ksy::generate("seq: []"),
+        Err(Error::Invalid(_))
+    ));
+    assert!(matches!(
+        declio_ksy::generate("meta: [unclosed"),
+        Err(Error::Yaml(_))
+    ));
+}
@@ -0,0 +1,76 @@
+// Generated by declio_ksy from the `packet` Kaitai Struct specification. Do not edit.
+
+/// A framed packet.
+#[derive(Debug, Clone, PartialEq, declio::Encode, declio::Decode)]
+#[declio(endian = "big")]
+pub struct Packet {
+    pub magic: PacketMagic,
+    pub kind: Kind,
+    pub len: u16,
+    #[declio(endian = "little")]
+    pub checksum: u32,
+    #[declio(ctx = "declio::ctx::Len(*len as usize)")]
+    pub body: Vec<u8>,
+    pub count: u8,
+    #[declio(ctx = "declio::ctx::Len(*count as usize)")]
+    pub entries: Vec<Entry>,
+    #[declio(ctx = "declio::ctx::Until(0)")]
+    pub name: declio::util::Utf8,
+    #[declio(endian, skip_if = "((*kind as i64) != (Kind::Data as i64)) || ((*len as i64) <= 0)")]
+    pub extra: Option<i16>,
+    #[declio(ctx = "*kind as i64")]
+    pub payload: PacketPayload,
+    #[declio(byte_len = "2", skip_if = "(entries.len() as i64) <= 1")]
+    pub header: Option<Header>,
+    pub version: Header,
+    #[declio(ctx = "declio::ctx::Len(((version.version as i64) * 2) as usize)")]
+    pub tail: Vec<u8>,
+    /// Unlike in Kaitai Struct, the terminating `0` is not included.
+    #[declio(ctx = "(declio::ctx::Until(0), declio::ctx::Endian::Big)")]
+    pub trailer: Vec<u16>,
+}
+
+declio::magic_bytes! {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub PacketMagic(b"PK");
+}
+
+#[derive(Debug, Clone, PartialEq, declio::Encode, declio::Decode)]
+#[declio(ctx = "id: i64", id_expr = "id", endian = "big")]
+pub enum PacketPayload {
+    #[declio(id = "Kind::Ping as i64")]
+    Ping(Ping),
+    #[declio(id = "Kind::Data as i64")]
+    Header(Header),
+}
+
+/// A key-value pair.
+#[derive(Debug, Clone, PartialEq, declio::Encode, declio::Decode)]
+#[declio(endian = "big")]
+pub struct Entry {
+    pub key: u8,
+    #[declio(endian = "little")]
+    pub value: i16,
+}
+
+#[derive(Debug, Clone, PartialEq, declio::Encode, declio::Decode)]
+#[declio(endian = "big")]
+pub struct Ping;
+
+#[derive(Debug, Clone, PartialEq, declio::Encode, declio::Decode)]
+#[declio(endian = "big")]
+pub struct Header {
+    pub version: u8,
+    pub r#type: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, declio::Encode, declio::Decode)]
+#[declio(id_type = "u8")]
+#[repr(u8)]
+pub enum Kind {
+    #[declio(id = "0")]
+    Ping = 0,
+    /// Carries a payload.
+    #[declio(id = "1")]
+    Data = 1,
+}